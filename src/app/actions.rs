@@ -21,14 +21,26 @@ pub enum Action {
     TakeUserInput,
     Escape,
     Enter,
+    Toggle,
+    MarkAll,
+    FilterType,
+    ShowFrameworks,
+    ShowLoadOrder,
+    ShowRedmods,
+    ShowProfiles,
+    ShowJobs,
+    Delete,
+    MoveUp,
+    MoveDown,
     LogHelp,
     SaveSettings,
+    Cancel,
 }
 
 impl Action {
     /// All available actions
     pub fn iterator() -> Iter<'static, Action> {
-        static ACTIONS: [Action; 13] = [
+        static ACTIONS: [Action; 25] = [
             Action::Quit,
             Action::Tab,
             Action::ShiftTab,
@@ -40,8 +52,20 @@ impl Action {
             Action::TakeUserInput,
             Action::Escape,
             Action::Enter,
+            Action::Toggle,
+            Action::MarkAll,
+            Action::FilterType,
+            Action::ShowFrameworks,
+            Action::ShowLoadOrder,
+            Action::ShowRedmods,
+            Action::ShowProfiles,
+            Action::ShowJobs,
+            Action::Delete,
+            Action::MoveUp,
+            Action::MoveDown,
             Action::LogHelp,
-            Action::SaveSettings
+            Action::SaveSettings,
+            Action::Cancel
         ];
         ACTIONS.iter()
     }
@@ -60,8 +84,20 @@ impl Action {
             Action::TakeUserInput => &[Key::Char('i')],
             Action::Escape => &[Key::Esc],
             Action::Enter => &[Key::Enter],
+            Action::Toggle => &[Key::Char(' ')],
+            Action::MarkAll => &[Key::Char('a')],
+            Action::FilterType => &[Key::Char('t')],
+            Action::ShowFrameworks => &[Key::Char('w')],
+            Action::ShowLoadOrder => &[Key::Char('o')],
+            Action::ShowRedmods => &[Key::Char('r')],
+            Action::ShowProfiles => &[Key::Char('p')],
+            Action::ShowJobs => &[Key::Char('j')],
+            Action::Delete => &[Key::Delete],
+            Action::MoveUp => &[Key::Char('+')],
+            Action::MoveDown => &[Key::Char('-')],
             Action::LogHelp => &[Key::Char('h')],
-            Action::SaveSettings => &[Key::Ctrl('s')]
+            Action::SaveSettings => &[Key::Ctrl('s')],
+            Action::Cancel => &[Key::Char('x')]
         }
     }
 
@@ -85,8 +121,20 @@ impl Display for Action {
             Action::TakeUserInput => "Enter input mode",
             Action::Escape => "Go to previous mode",
             Action::Enter => "Accept",
+            Action::Toggle => "Toggle option",
+            Action::MarkAll => "Mark every shown mod",
+            Action::FilterType => "Filter by mod type",
+            Action::ShowFrameworks => "Show installed frameworks",
+            Action::ShowLoadOrder => "Show archive load order",
+            Action::ShowRedmods => "Show REDmods",
+            Action::ShowProfiles => "Show profiles",
+            Action::ShowJobs => "Show background jobs",
+            Action::Delete => "Delete",
+            Action::MoveUp => "Load earlier",
+            Action::MoveDown => "Load later",
             Action::LogHelp => "Show help",
-            Action::SaveSettings => "Save settings"
+            Action::SaveSettings => "Save settings",
+            Action::Cancel => "Cancel the running operation"
         };
         write!(f, "{}", str)
     }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::Path;
 use std::vec;
 use std::path::PathBuf;
@@ -12,16 +13,26 @@ use self::state::AppState;
 use self::state::AppStatus;
 use self::state::Focus;
 use self::state::UiMode;
-use self::utils::{ModPopup, ModOptions};
+use self::utils::{ChoicePopup, FomodWizard, ModPopup, ModOptions, StatefulList};
 use self::utils::check_if_mod_is_valid;
 use self::utils::log_help;
 use crate::app::actions::Action;
 use crate::constants::CYBERPUNK_FOLDER_INPUT_EMPTY_ERROR;
+use crate::constants::DEFAULT_EXTRACTION_CACHE_LIMIT_MB;
 use crate::constants::MOD_FOLDER_INPUT_EMPTY_ERROR;
 use crate::constants::NOT_A_DIRECTORY_ERROR;
 use crate::constants::NOT_A_VALID_CYBERPUNK_FOLDER_ERROR;
 use crate::inputs::key::Key;
 use crate::io::IoEvent;
+use crate::io::batch::BatchOperation;
+use crate::io::conflicts::ConflictResolution;
+use crate::io::frameworks::FrameworkInfo;
+use crate::io::load_order::LoadOrderPosition;
+use crate::io::mod_type::{ModClassification, ModType};
+use crate::io::jobs::{JobEvent, JobList, JobState};
+use crate::io::plan::{PlanPreview, PlannedUninstall};
+use crate::io::progress::ProgressHandle;
+use crate::io::status::ModInstallStatus;
 
 pub mod actions;
 pub mod state;
@@ -35,17 +46,36 @@ pub enum AppReturn {
 }
 
 pub struct App {
-    io_tx: tokio::sync::mpsc::Sender<IoEvent>,
+    io_tx: tokio::sync::mpsc::Sender<JobEvent>,
     actions: Actions,
     is_loading: bool,
     pub mod_popup: Option<ModPopup>,
+    pub choice_popup: Option<ChoicePopup>,
+    pub fomod_wizard: Option<FomodWizard>,
+    /// What the next install, uninstall or profile switch changes, it runs once this is confirmed
+    pub plan_preview: Option<PlanPreview>,
+    /// Uninstalls worked out for a preview by mod name, confirming them does not check the files again
+    pub planned_uninstalls: HashMap<String, PlannedUninstall>,
+    pub mod_statuses: HashMap<String, ModInstallStatus>,
+    pub mod_types: HashMap<String, ModClassification>,
+    pub frameworks: Vec<FrameworkInfo>,
+    /// Where archives of new installs go in the load order
+    pub new_archive_position: LoadOrderPosition,
+    /// The profile last saved or switched to
+    pub active_profile: Option<String>,
+    /// Progress of the running install, uninstall or repair
+    pub progress: ProgressHandle,
+    /// Jobs dispatched to the IO thread this session
+    pub jobs: JobList,
+    /// Size limit of the extraction cache in megabytes
+    pub extraction_cache_limit_mb: u64,
     pub state: AppState,
     pub mod_folder: Option<PathBuf>,
     pub cyberpunk_folder: Option<PathBuf>,
 }
 
 impl App {
-    pub fn new(io_tx: tokio::sync::mpsc::Sender<IoEvent>) -> Self {
+    pub fn new(io_tx: tokio::sync::mpsc::Sender<JobEvent>) -> Self {
         let actions = vec![Action::Quit].into();
         let is_loading = false;
         let state = AppState::default();
@@ -56,6 +86,18 @@ impl App {
             actions,
             is_loading,
             mod_popup,
+            choice_popup: None,
+            fomod_wizard: None,
+            plan_preview: None,
+            planned_uninstalls: HashMap::new(),
+            mod_statuses: HashMap::new(),
+            mod_types: HashMap::new(),
+            frameworks: vec![],
+            new_archive_position: LoadOrderPosition::default(),
+            active_profile: None,
+            progress: ProgressHandle::default(),
+            jobs: JobList::default(),
+            extraction_cache_limit_mb: DEFAULT_EXTRACTION_CACHE_LIMIT_MB,
             state,
             mod_folder: None,
             cyberpunk_folder: None,
@@ -64,6 +106,19 @@ impl App {
 
     /// Handle a user action
     pub async fn do_action(&mut self, key: Key) -> AppReturn {
+        // long operations release the app while they run, they still read the popup they started from
+        if self.is_loading {
+            let action = self.actions.find(key).copied();
+            if self.state.status != AppStatus::UserInput && action == Some(Action::Cancel) && self.progress.get().is_some() {
+                info!("🚀 Cancelling, the changes made so far are rolled back");
+                self.progress.cancel();
+                return AppReturn::Continue;
+            }
+            if action.is_some_and(|action| self.waits_for_running_operation(action)) {
+                warn!("⚠️ Wait for the running operation to finish, or press <x> to cancel it");
+                return AppReturn::Continue;
+            }
+        }
         // check if we are in a user input mode
         if self.state.status == AppStatus::UserInput {
             // append to current user input if key is not enter else change state to Initialized
@@ -78,7 +133,7 @@ impl App {
                 } else if current_key == "<Backspace>" {
                     match self.state.focus {
                         Focus::ModFolderInput => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position > 0 {
                                 self.state.select_folder_form[0].remove(cursor_position - 1);
                                 self.state.cursor_position = Some(cursor_position - 1);
@@ -87,7 +142,7 @@ impl App {
                             }
                         },
                         Focus::CyberpunkFolderInput => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position > 0 {
                                 self.state.select_folder_form[1].remove(cursor_position - 1);
                                 self.state.cursor_position = Some(cursor_position - 1);
@@ -96,7 +151,7 @@ impl App {
                             }
                         },
                         _ => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position > 0 {
                                 self.state.current_input.remove(cursor_position - 1);
                                 self.state.cursor_position = Some(cursor_position - 1);
@@ -110,19 +165,19 @@ impl App {
                 } else if current_key == "<Left>" {
                     match self.state.focus {
                         Focus::ModFolderInput => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position > 0 {
                                 self.state.cursor_position = Some(cursor_position - 1);
                             }
                         },
                         Focus::CyberpunkFolderInput => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position > 0 {
                                 self.state.cursor_position = Some(cursor_position - 1);
                             }
                         },
                         _ => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position > 0 {
                                 self.state.cursor_position = Some(cursor_position - 1);
                             }
@@ -132,19 +187,19 @@ impl App {
                 } else if current_key == "<Right>" {
                     match self.state.focus {
                         Focus::ModFolderInput => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position < self.state.select_folder_form[0].len() {
                                 self.state.cursor_position = Some(cursor_position + 1);
                             }
                         },
                         Focus::CyberpunkFolderInput => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position < self.state.select_folder_form[1].len() {
                                 self.state.cursor_position = Some(cursor_position + 1);
                             }
                         },
                         _ => {
-                            let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
                             if cursor_position < self.state.current_input.len() {
                                 self.state.cursor_position = Some(cursor_position + 1);
                             }
@@ -181,32 +236,47 @@ impl App {
                     current_key = current_key[1..current_key.len() - 1].to_string();
                 }
 
-                if self.state.focus == Focus::ModFolderInput && current_key != "" {
-                    let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                if self.state.focus == Focus::ModFolderInput && !current_key.is_empty() {
+                    let cursor_position = self.state.cursor_position.unwrap_or(0);
                     self.state.select_folder_form[0].insert(cursor_position, current_key.chars().next().unwrap());
                     self.state.cursor_position = Some(cursor_position + 1);
-                } else if self.state.focus == Focus::CyberpunkFolderInput && current_key != "" {
-                    let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                } else if self.state.focus == Focus::CyberpunkFolderInput && !current_key.is_empty() {
+                    let cursor_position = self.state.cursor_position.unwrap_or(0);
                     self.state.select_folder_form[1].insert(cursor_position, current_key.chars().next().unwrap());
                     self.state.cursor_position = Some(cursor_position + 1);
-                } else if current_key != "" {
-                    let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                } else if !current_key.is_empty() {
+                    let cursor_position = self.state.cursor_position.unwrap_or(0);
                     self.state.current_input.insert(cursor_position, current_key.chars().next().unwrap());
                     self.state.cursor_position = Some(cursor_position + 1);
                 }
             } else {
                 self.state.status = AppStatus::Initialized;
                 debug!("Exiting user input mode");
+                if self.state.ui_mode == UiMode::Profiles {
+                    let profile_name = self.state.current_input.trim().to_string();
+                    self.state.current_input.clear();
+                    self.state.cursor_position = None;
+                    if key == Key::Enter && !profile_name.is_empty() {
+                        self.dispatch(IoEvent::SaveProfile(profile_name)).await;
+                    }
+                }
             }
-            return AppReturn::Continue;
+            AppReturn::Continue
         } else {
             if let Some(action) = self.actions.find(key) {
+                if self.plan_preview.is_some() {
+                    return self.do_plan_preview_action(*action).await;
+                }
+                // the job list can be opened over a mod popup, keys meant for the popup wait until it is closed
+                if self.state.ui_mode == UiMode::Jobs && !matches!(action, Action::ShowJobs | Action::Escape | Action::Quit | Action::LogHelp | Action::Cancel) {
+                    return AppReturn::Continue;
+                }
                 match action {
                     Action::Quit => {
                         AppReturn::Exit
                     }
                     Action::Tab => {
-                        let current_focus = self.state.focus.clone();
+                        let current_focus = self.state.focus;
                         let next_focus = self.state.focus.next(&UiMode::get_available_targets(&self.state.ui_mode));
                         // check if the next focus is the same as the current focus or NoFocus if so set back to the first focus
                         if next_focus == current_focus || next_focus == Focus::NoFocus {
@@ -217,7 +287,7 @@ impl App {
                         AppReturn::Continue
                     }
                     Action::ShiftTab => {
-                        let current_focus = self.state.focus.clone();
+                        let current_focus = self.state.focus;
                         let next_focus = self.state.focus.prev(&UiMode::get_available_targets(&self.state.ui_mode));
                         // check if the next focus is the same as the current focus or NoFocus if so set back to the first focus
                         if next_focus == current_focus || next_focus == Focus::NoFocus {
@@ -228,16 +298,36 @@ impl App {
                         AppReturn::Continue
                     }
                     Action::Up => {
-                        if self.mod_popup.is_some() {
+                        if let Some(choice_popup) = self.choice_popup.as_mut() {
+                            choice_popup.choices.previous();
+                        } else if let Some(fomod_wizard) = self.fomod_wizard.as_mut() {
+                            fomod_wizard.options.previous();
+                        } else if self.mod_popup.is_some() {
                             self.state.mod_options.previous();
+                        } else if self.state.ui_mode == UiMode::LoadOrder {
+                            self.state.load_order.previous();
+                        } else if self.state.ui_mode == UiMode::Redmods {
+                            self.state.redmods.previous();
+                        } else if self.state.ui_mode == UiMode::Profiles {
+                            self.state.profiles.previous();
                         } else {
                             self.state.file_list.previous()
                         }
                         AppReturn::Continue
                     }
                     Action::Down => {
-                        if self.mod_popup.is_some() {
+                        if let Some(choice_popup) = self.choice_popup.as_mut() {
+                            choice_popup.choices.next();
+                        } else if let Some(fomod_wizard) = self.fomod_wizard.as_mut() {
+                            fomod_wizard.options.next();
+                        } else if self.mod_popup.is_some() {
                             self.state.mod_options.next();
+                        } else if self.state.ui_mode == UiMode::LoadOrder {
+                            self.state.load_order.next();
+                        } else if self.state.ui_mode == UiMode::Redmods {
+                            self.state.redmods.next();
+                        } else if self.state.ui_mode == UiMode::Profiles {
+                            self.state.profiles.next();
                         } else {
                             self.state.file_list.next()
                         }
@@ -246,13 +336,13 @@ impl App {
                     Action::Right => {
                         if self.state.status == AppStatus::UserInput {
                             if self.state.focus == Focus::ModFolderInput {
-                                let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                                let cursor_position = self.state.cursor_position.unwrap_or(0);
                                 self.state.cursor_position = Some((cursor_position + 1).min(self.state.select_folder_form[0].len()));
                             } else if self.state.focus == Focus::CyberpunkFolderInput {
-                                let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                                let cursor_position = self.state.cursor_position.unwrap_or(0);
                                 self.state.cursor_position = Some((cursor_position + 1).min(self.state.select_folder_form[1].len()));
                             } else {
-                                let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
+                                let cursor_position = self.state.cursor_position.unwrap_or(0);
                                 self.state.cursor_position = Some((cursor_position + 1).min(self.state.current_input.len()));
                             }
                         }
@@ -260,76 +350,103 @@ impl App {
                     }
                     Action::Left => {
                         if self.state.status == AppStatus::UserInput {
-                            if self.state.focus == Focus::ModFolderInput {
-                                let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
-                                if cursor_position > 0 {
-                                    self.state.cursor_position = Some(cursor_position - 1);
-                                }
-                            } else if self.state.focus == Focus::CyberpunkFolderInput {
-                                let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
-                                if cursor_position > 0 {
-                                    self.state.cursor_position = Some(cursor_position - 1);
-                                }
-                            } else {
-                                let cursor_position = self.state.cursor_position.unwrap_or_else(||0);
-                                if cursor_position > 0 {
-                                    self.state.cursor_position = Some(cursor_position - 1);
-                                }
+                            let cursor_position = self.state.cursor_position.unwrap_or(0);
+                            if cursor_position > 0 {
+                                self.state.cursor_position = Some(cursor_position - 1);
                             }
                         }
                         AppReturn::Continue
                     }
                     Action::TakeUserInput => {
-                        match self.state.ui_mode {
-                            UiMode::SelectFolder => {
-                                self.state.status = AppStatus::UserInput;
-                                if self.state.focus == Focus::ModFolderInput {
-                                    if self.state.select_folder_form[0].ends_with(NOT_A_DIRECTORY_ERROR) {
-                                        self.state.select_folder_form[0] = self.state.select_folder_form[0]
-                                            .replace(NOT_A_DIRECTORY_ERROR, "").trim().to_string();
-                                    } else if self.state.select_folder_form[0].ends_with(MOD_FOLDER_INPUT_EMPTY_ERROR) {
-                                        self.state.select_folder_form[0] = self.state.select_folder_form[0]
-                                            .replace(MOD_FOLDER_INPUT_EMPTY_ERROR, "").trim().to_string();
-                                    }
-                                    // ensure the cursor is at the end of the string
-                                    self.state.cursor_position = Some(self.state.select_folder_form[0].len());
-                                } else if self.state.focus == Focus::CyberpunkFolderInput {
-                                    if self.state.select_folder_form[1].ends_with(NOT_A_DIRECTORY_ERROR) {
-                                        self.state.select_folder_form[1] = self.state.select_folder_form[1]
-                                            .replace(NOT_A_DIRECTORY_ERROR, "").trim().to_string();
-                                    } else if self.state.select_folder_form[1].ends_with(CYBERPUNK_FOLDER_INPUT_EMPTY_ERROR) {
-                                        self.state.select_folder_form[1] = self.state.select_folder_form[1]
-                                            .replace(CYBERPUNK_FOLDER_INPUT_EMPTY_ERROR, "").trim().to_string();
-                                    } else if self.state.select_folder_form[1].ends_with(NOT_A_VALID_CYBERPUNK_FOLDER_ERROR) {
-                                        self.state.select_folder_form[1] = self.state.select_folder_form[1]
-                                            .replace(NOT_A_VALID_CYBERPUNK_FOLDER_ERROR, "").trim().to_string();
-                                    }
-                                    // ensure the cursor is at the end of the string
-                                    self.state.cursor_position = Some(self.state.select_folder_form[1].len());
+                        if self.state.ui_mode == UiMode::SelectFolder {
+                            self.state.status = AppStatus::UserInput;
+                            if self.state.focus == Focus::ModFolderInput {
+                                if self.state.select_folder_form[0].ends_with(NOT_A_DIRECTORY_ERROR) {
+                                    self.state.select_folder_form[0] = self.state.select_folder_form[0]
+                                        .replace(NOT_A_DIRECTORY_ERROR, "").trim().to_string();
+                                } else if self.state.select_folder_form[0].ends_with(MOD_FOLDER_INPUT_EMPTY_ERROR) {
+                                    self.state.select_folder_form[0] = self.state.select_folder_form[0]
+                                        .replace(MOD_FOLDER_INPUT_EMPTY_ERROR, "").trim().to_string();
                                 }
+                                // ensure the cursor is at the end of the string
+                                self.state.cursor_position = Some(self.state.select_folder_form[0].len());
+                            } else if self.state.focus == Focus::CyberpunkFolderInput {
+                                if self.state.select_folder_form[1].ends_with(NOT_A_DIRECTORY_ERROR) {
+                                    self.state.select_folder_form[1] = self.state.select_folder_form[1]
+                                        .replace(NOT_A_DIRECTORY_ERROR, "").trim().to_string();
+                                } else if self.state.select_folder_form[1].ends_with(CYBERPUNK_FOLDER_INPUT_EMPTY_ERROR) {
+                                    self.state.select_folder_form[1] = self.state.select_folder_form[1]
+                                        .replace(CYBERPUNK_FOLDER_INPUT_EMPTY_ERROR, "").trim().to_string();
+                                } else if self.state.select_folder_form[1].ends_with(NOT_A_VALID_CYBERPUNK_FOLDER_ERROR) {
+                                    self.state.select_folder_form[1] = self.state.select_folder_form[1]
+                                        .replace(NOT_A_VALID_CYBERPUNK_FOLDER_ERROR, "").trim().to_string();
+                                }
+                                // ensure the cursor is at the end of the string
+                                self.state.cursor_position = Some(self.state.select_folder_form[1].len());
                             }
-                            _ => {}
+                        } else if self.state.ui_mode == UiMode::Profiles {
+                            // the name of the profile to save
+                            self.state.status = AppStatus::UserInput;
+                            self.state.current_input.clear();
+                            self.state.cursor_position = Some(0);
                         }
                         AppReturn::Continue
                     }
                     Action::Escape => {
+                        // a pending question is dismissed before anything else
+                        if self.choice_popup.take().is_some() {
+                            return AppReturn::Continue;
+                        }
+                        if self.state.ui_mode == UiMode::Jobs {
+                            self.toggle_jobs();
+                            return AppReturn::Continue;
+                        }
+                        if let Some(fomod_wizard) = self.fomod_wizard.as_mut() {
+                            if !fomod_wizard.previous_step() {
+                                self.fomod_wizard = None;
+                                info!("Installer closed, nothing was installed");
+                            }
+                            return AppReturn::Continue;
+                        }
                         if self.state.status == AppStatus::UserInput {
                             self.state.status = AppStatus::Initialized;
                             self.state.cursor_position = None;
                         } else if self.state.status == AppStatus::Initialized {
-                            if self.state.ui_mode == UiMode::SelectFolder {
+                            if matches!(self.state.ui_mode, UiMode::SelectFolder | UiMode::Frameworks | UiMode::LoadOrder | UiMode::Redmods | UiMode::Profiles) {
                                 self.state.ui_mode = UiMode::Explore;
                             } else if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
-                                return AppReturn::Exit;
+                                if self.state.marked.is_empty() {
+                                    return AppReturn::Exit;
+                                }
+                                self.state.marked.clear();
+                                info!("Marks cleared");
                             }
                         }
-                        if self.mod_popup.is_some() {
-                            self.mod_popup = None;
+                        // closing the popup of a framework goes back to the mod that needs it
+                        if let Some(mod_popup) = self.mod_popup.take() {
+                            self.mod_popup = mod_popup.resume_after.map(|mod_popup| *mod_popup);
                         }
                         
                         AppReturn::Continue
                     }
                     Action::Enter => {
+                        if let Some(choice_popup) = self.choice_popup.take() {
+                            if let Some(event) = choice_popup.selected_event() {
+                                self.dispatch(event).await;
+                            }
+                            return AppReturn::Continue;
+                        }
+                        if let Some(fomod_wizard) = self.fomod_wizard.as_mut() {
+                            let unmet_groups = fomod_wizard.unmet_groups();
+                            if !unmet_groups.is_empty() {
+                                warn!("⚠️ Pick an option in {}", unmet_groups.join(", "));
+                            } else if !fomod_wizard.next_step() {
+                                let fomod_choices = fomod_wizard.choices.clone();
+                                self.fomod_wizard = None;
+                                self.dispatch(IoEvent::SelectFomodChoices(fomod_choices)).await;
+                            }
+                            return AppReturn::Continue;
+                        }
                         if self.state.status == AppStatus::UserInput {
                             self.state.status = AppStatus::Initialized;
                             self.state.cursor_position = None;
@@ -337,6 +454,13 @@ impl App {
                         if self.state.focus == Focus::Submit {
                             self.dispatch(IoEvent::LoadMods).await;
                         }
+                        if self.state.ui_mode == UiMode::Profiles {
+                            if let Some(profile) = self.state.profiles.state.selected()
+                                .and_then(|selected| self.state.profiles.items.get(selected)) {
+                                let profile_name = profile.name.clone();
+                                self.dispatch(IoEvent::SwitchProfile(profile_name)).await;
+                            }
+                        }
                         if self.state.ui_mode == UiMode::Explore {
                             if self.mod_popup.is_some() {
                                 let current_selected_option_index = self.state.mod_options.state.selected();
@@ -351,16 +475,25 @@ impl App {
                                             self.dispatch(IoEvent::UninstallMod).await;
                                         }
                                         ModOptions::Repair => {
-                                            self.dispatch(IoEvent::UninstallMod).await;
-                                            self.dispatch(IoEvent::InstallMod).await;
+                                            self.dispatch(IoEvent::RepairMod).await;
+                                        }
+                                        ModOptions::Disable => {
+                                            self.dispatch(IoEvent::DisableMod).await;
+                                        }
+                                        ModOptions::Enable => {
+                                            self.dispatch(IoEvent::EnableMod).await;
                                         }
                                     }
                                 }
                             }
-                            else if let Some(selected) = self.state.file_list.state.selected() {
-                                let selected_file = self.state.file_list.items[selected].0.clone();
+                            else if !self.state.marked.is_empty() {
+                                self.choice_popup = Some(batch_popup(&self.state.marked));
+                            }
+                            else if let Some((selected_file, _)) = self.state.file_list.state.selected()
+                                .and_then(|selected| self.state.file_list.items.get(selected)) {
+                                let selected_file = selected_file.clone();
                                 let selected_file_path = Path::new(&self.mod_folder.as_ref().unwrap()).join(&selected_file);
-                                if !check_if_mod_is_valid(selected_file_path.clone()) {
+                                if !check_if_mod_is_valid(selected_file_path.clone(), self.mod_types.get(&selected_file)) {
                                     error!("{} is not a valid mod", selected_file_path.to_string_lossy());
                                 } else {
                                     info!("Selected mod: {}", selected_file_path.to_string_lossy());
@@ -393,6 +526,151 @@ impl App {
                         }
                         AppReturn::Continue
                     }
+                    Action::Toggle => {
+                        if let Some(fomod_wizard) = self.fomod_wizard.as_mut() {
+                            fomod_wizard.toggle_selected();
+                        } else if self.state.ui_mode == UiMode::LoadOrder {
+                            self.new_archive_position = self.new_archive_position.toggle();
+                            info!(
+                                "New archives now go to the {} of the load order, press <Ctrl + s> to keep this",
+                                self.new_archive_position.to_str()
+                            );
+                        } else if self.state.ui_mode == UiMode::Redmods {
+                            if let Some(redmod) = self.state.redmods.state.selected()
+                                .and_then(|selected| self.state.redmods.items.get(selected)) {
+                                let folder = redmod.folder.clone();
+                                self.dispatch(IoEvent::ToggleRedmod(folder)).await;
+                            }
+                        } else if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
+                            if let Some((selected_file, _)) = self.state.file_list.state.selected()
+                                .and_then(|selected| self.state.file_list.items.get(selected)) {
+                                let selected_file = selected_file.clone();
+                                if let Some(position) = self.state.marked.iter().position(|marked| *marked == selected_file) {
+                                    self.state.marked.remove(position);
+                                } else {
+                                    self.state.marked.push(selected_file);
+                                }
+                            }
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::MarkAll => {
+                        if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
+                            let shown: Vec<String> = self.state.file_list.items.iter().map(|(name, _)| name.clone()).collect();
+                            if shown.iter().all(|name| self.state.marked.contains(name)) {
+                                self.state.marked.retain(|name| !shown.contains(name));
+                                info!("Unmarked {} mods", shown.len());
+                            } else {
+                                for name in shown {
+                                    if !self.state.marked.contains(&name) {
+                                        self.state.marked.push(name);
+                                    }
+                                }
+                                info!("{} mods marked, press <Enter> to pick what to do with them", self.state.marked.len());
+                            }
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::FilterType => {
+                        if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
+                            // cycle through every type and back to showing everything
+                            let types = ModType::all();
+                            self.state.type_filter = match self.state.type_filter {
+                                None => types.first().copied(),
+                                Some(current) => types
+                                    .iter()
+                                    .position(|mod_type| *mod_type == current)
+                                    .and_then(|index| types.get(index + 1).copied()),
+                            };
+                            self.apply_type_filter();
+                            match self.state.type_filter {
+                                Some(mod_type) => info!("Showing {} mods", mod_type.to_str()),
+                                None => info!("Showing all mods"),
+                            }
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::ShowFrameworks => {
+                        if self.state.ui_mode == UiMode::Frameworks {
+                            self.state.ui_mode = UiMode::Explore;
+                        } else if self.cyberpunk_folder.is_none() {
+                            error!("Select the Cyberpunk folder first");
+                        } else if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
+                            self.state.ui_mode = UiMode::Frameworks;
+                            self.dispatch(IoEvent::DetectFrameworks).await;
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::ShowLoadOrder => {
+                        if self.state.ui_mode == UiMode::LoadOrder {
+                            self.state.ui_mode = UiMode::Explore;
+                        } else if self.cyberpunk_folder.is_none() {
+                            error!("Select the Cyberpunk folder first");
+                        } else if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
+                            self.state.ui_mode = UiMode::LoadOrder;
+                            self.dispatch(IoEvent::LoadLoadOrder).await;
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::ShowRedmods => {
+                        if self.state.ui_mode == UiMode::Redmods {
+                            self.state.ui_mode = UiMode::Explore;
+                        } else if self.cyberpunk_folder.is_none() {
+                            error!("Select the Cyberpunk folder first");
+                        } else if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
+                            self.state.ui_mode = UiMode::Redmods;
+                            self.dispatch(IoEvent::LoadRedmods).await;
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::ShowProfiles => {
+                        if self.state.ui_mode == UiMode::Profiles {
+                            self.state.ui_mode = UiMode::Explore;
+                        } else if self.cyberpunk_folder.is_none() || self.mod_folder.is_none() {
+                            error!("Select the Mod and Cyberpunk folders first");
+                        } else if self.state.ui_mode == UiMode::Explore && self.mod_popup.is_none() {
+                            self.state.ui_mode = UiMode::Profiles;
+                            if self.state.profiles.state.selected().is_none() && !self.state.profiles.items.is_empty() {
+                                self.state.profiles.next();
+                            }
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::ShowJobs => {
+                        self.toggle_jobs();
+                        AppReturn::Continue
+                    }
+                    Action::Delete => {
+                        if self.state.ui_mode == UiMode::Profiles {
+                            if let Some(selected) = self.state.profiles.state.selected()
+                                .filter(|selected| *selected < self.state.profiles.items.len()) {
+                                let profile = self.state.profiles.items.remove(selected);
+                                if self.active_profile.as_ref() == Some(&profile.name) {
+                                    self.active_profile = None;
+                                }
+                                self.state.profiles.unselect();
+                                if !self.state.profiles.items.is_empty() {
+                                    self.state.profiles.state.select(Some(selected.min(self.state.profiles.items.len() - 1)));
+                                }
+                                info!("Profile {} deleted", profile.name);
+                                self.dispatch(IoEvent::SaveSettings).await;
+                            }
+                        }
+                        AppReturn::Continue
+                    }
+                    Action::MoveUp | Action::MoveDown => {
+                        let earlier = *action == Action::MoveUp;
+                        if self.state.ui_mode == UiMode::LoadOrder {
+                            if move_selected(&mut self.state.load_order, earlier) {
+                                let archives = self.state.load_order.items.iter().map(|(archive, _)| archive.clone()).collect();
+                                self.dispatch(IoEvent::SaveLoadOrder(archives)).await;
+                            }
+                        } else if self.state.ui_mode == UiMode::Redmods && move_selected(&mut self.state.redmods, earlier) {
+                            let folders = self.state.redmods.items.iter().map(|redmod| redmod.folder.clone()).collect();
+                            self.dispatch(IoEvent::SaveRedmodOrder(folders)).await;
+                        }
+                        AppReturn::Continue
+                    }
                     Action::LogHelp => {
                         log_help();
                         AppReturn::Continue
@@ -401,6 +679,10 @@ impl App {
                         self.dispatch(IoEvent::SaveSettings).await;
                         AppReturn::Continue
                     }
+                    Action::Cancel => {
+                        info!("Nothing to cancel");
+                        AppReturn::Continue
+                    }
                 }
             } else {
                 warn!("No action accociated to {}", key);
@@ -409,15 +691,98 @@ impl App {
         }
     }
     
+    /// Keys that start an operation or close a popup a running operation reads from wait until it finished
+    fn waits_for_running_operation(&self, action: Action) -> bool {
+        match action {
+            Action::Enter | Action::SelectFolder | Action::Delete | Action::MoveUp | Action::MoveDown | Action::SaveSettings => true,
+            Action::Escape => {
+                self.choice_popup.is_some()
+                    || (self.state.ui_mode != UiMode::Jobs && (self.mod_popup.is_some() || self.fomod_wizard.is_some()))
+            }
+            // toggling a REDmod moves its folder
+            Action::Toggle => self.state.ui_mode == UiMode::Redmods,
+            _ => false,
+        }
+    }
+
     /// Send a network event to the IO thread
     pub async fn dispatch(&mut self, action: IoEvent) {
-        // `is_loading` will be set to false again after the async action has finished in io/handler.rs
-        self.is_loading = true;
-        if let Err(e) = self.io_tx.send(action).await {
-            self.is_loading = false;
+        // `is_loading` will be set to false again once no serialized job is left, see io/jobs.rs
+        let serialized = action.is_serialized();
+        if serialized {
+            self.is_loading = true;
+        }
+        let job_id = self.jobs.add(action.description(), serialized);
+        if let Err(e) = self.io_tx.send((job_id, action)).await {
+            self.jobs.set_state(job_id, JobState::Failed(e.to_string()));
+            if !self.jobs.serialized_pending() {
+                self.is_loading = false;
+            }
             error!("Error from dispatch {}", e);
         };
     }
+    /// Keys while a plan is shown, it is either confirmed or cancelled before anything else happens
+    async fn do_plan_preview_action(&mut self, action: Action) -> AppReturn {
+        match action {
+            Action::Quit => return AppReturn::Exit,
+            Action::Enter => {
+                let plan_preview = self.plan_preview.take().unwrap();
+                // a profile switch or a batch plans its own mods, the open popup was not part of it
+                let plans_own_mods = matches!(plan_preview.confirm, IoEvent::ApplyProfile(_) | IoEvent::ApplyBatch(..));
+                if let Some(mod_popup) = self.mod_popup.as_mut().filter(|_| !plans_own_mods) {
+                    mod_popup.plan_confirmed = true;
+                }
+                self.dispatch(plan_preview.confirm).await;
+            }
+            Action::Escape => {
+                self.plan_preview = None;
+                info!("👍 Cancelled, nothing was changed");
+            }
+            Action::Up => {
+                let plan_preview = self.plan_preview.as_mut().unwrap();
+                plan_preview.scroll = plan_preview.scroll.saturating_sub(1);
+            }
+            Action::Down => {
+                let plan_preview = self.plan_preview.as_mut().unwrap();
+                plan_preview.scroll = plan_preview.scroll.saturating_add(1);
+            }
+            _ => {}
+        }
+        AppReturn::Continue
+    }
+
+    /// Switch between the explore view and the job list, the mod popup stays open below the job list
+    fn toggle_jobs(&mut self) {
+        if self.state.ui_mode == UiMode::Jobs {
+            self.state.ui_mode = UiMode::Explore;
+        } else if self.state.ui_mode == UiMode::Explore && self.fomod_wizard.is_none() {
+            self.state.ui_mode = UiMode::Jobs;
+        }
+    }
+    /// Rebuild the file list from every file of the mod folder that matches the type filter
+    pub fn apply_type_filter(&mut self) {
+        let files: Vec<(String, usize)> = match self.state.type_filter {
+            Some(mod_type) => self
+                .state
+                .all_files
+                .iter()
+                .filter(|(name, _)| {
+                    self.mod_types
+                        .get(name)
+                        .map(|classification| classification.has_type(mod_type))
+                        .unwrap_or_default()
+                })
+                .cloned()
+                .collect(),
+            None => self.state.all_files.clone(),
+        };
+        self.state.file_list.items = files;
+        self.state.file_list.unselect();
+        if !self.state.file_list.items.is_empty() {
+            self.state.file_list.next();
+        }
+    }
+
     pub fn actions(&self) -> &Actions {
         &self.actions
     }
@@ -442,4 +807,67 @@ impl App {
     pub fn change_focus(&mut self, focus: Focus) {
         self.state.focus = focus;
     }
-}
\ No newline at end of file
+}
+
+/// Swap the selected item with its neighbour, false when there is nothing to move
+fn move_selected<T>(list: &mut StatefulList<T>, earlier: bool) -> bool {
+    let Some(selected) = list.state.selected() else {
+        return false;
+    };
+    let target = if earlier {
+        selected.checked_sub(1)
+    } else {
+        Some(selected + 1).filter(|target| *target < list.items.len())
+    };
+    let Some(target) = target else {
+        return false;
+    };
+    list.items.swap(selected, target);
+    list.state.select(Some(target));
+    true
+}
+
+/// Ask what to do with the marked mods
+fn batch_popup(marked: &[String]) -> ChoicePopup {
+    let batch = |operation: BatchOperation| Some(IoEvent::RunBatch(operation, marked.to_vec()));
+    ChoicePopup::new(
+        "Marked mods".to_string(),
+        vec![
+            format!("{} mods marked: {}", marked.len(), marked.join(", ")),
+            "Frameworks go first, mods that need a decision are skipped and stay marked".to_string(),
+        ],
+        vec![
+            ("Install".to_string(), batch(BatchOperation::Install(ConflictResolution::Ask))),
+            ("Install, overwriting conflicting files".to_string(), batch(BatchOperation::Install(ConflictResolution::Overwrite))),
+            ("Install, skipping conflicting files".to_string(), batch(BatchOperation::Install(ConflictResolution::Skip))),
+            ("Uninstall".to_string(), batch(BatchOperation::Uninstall)),
+            ("Enable".to_string(), batch(BatchOperation::Enable)),
+            ("Disable".to_string(), batch(BatchOperation::Disable)),
+            ("Verify".to_string(), batch(BatchOperation::Verify)),
+            ("Cancel".to_string(), None),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_only_hold_back_conflicting_keys_while_loading() {
+        let (io_tx, _io_rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(io_tx);
+        app.actions = Action::iterator().copied().collect::<Vec<_>>().into();
+        app.state.file_list.items = vec![("a.zip".to_string(), 1), ("b.zip".to_string(), 1)];
+        app.state.file_list.next();
+        app.dispatch(IoEvent::UninstallMod).await;
+        assert!(app.is_loading());
+
+        app.do_action(Key::Down).await;
+        assert_eq!(app.state.file_list.state.selected(), Some(1));
+        // selecting a mod would replace the popup the running operation reads
+        app.do_action(Key::Enter).await;
+        assert!(app.mod_popup.is_none());
+        assert_eq!(app.do_action(Key::Char('q')).await, AppReturn::Exit);
+    }
+}
@@ -1,3 +1,9 @@
+use std::fmt;
+
+use crate::io::mod_type::ModType;
+use crate::io::profiles::Profile;
+use crate::io::redmod::Redmod;
+
 use super::utils::{StatefulList, ModOptions};
 
 #[derive(Clone, PartialEq, Debug)]
@@ -39,14 +45,11 @@ impl Focus {
         vec![Focus::Submit, Focus::ModFolderInput, Focus::CyberpunkFolderInput]
     }
 
-    pub fn next(&self, available_tabs: &Vec<String>) -> Self {
+    pub fn next(&self, available_tabs: &[String]) -> Self {
         let current = self.to_str();
         let index = available_tabs.iter().position(|x| x == current);
         // check if index is None
-        let index = match index {
-            Some(i) => i,
-            None => 0,
-        };
+        let index = index.unwrap_or_default();
         if available_tabs.len() <= 1 {
             return Self::NoFocus;
         }
@@ -59,14 +62,11 @@ impl Focus {
         }
     }
 
-    pub fn prev(&self, available_tabs: &Vec<String>) -> Self {
+    pub fn prev(&self, available_tabs: &[String]) -> Self {
         let current = self.to_str();
         let index = available_tabs.iter().position(|x| x == current);
         // check if index is None
-        let index = match index {
-            Some(i) => i,
-            None => 0,
-        };
+        let index = index.unwrap_or_default();
         let prev_index = if index == 0 {
             available_tabs.len() - 1
         } else {
@@ -80,7 +80,7 @@ impl Focus {
         }
     }
 
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_label(s: &str) -> Self {
         match s {
             "Submit" => Focus::Submit,
             "Mod Folder" => Focus::ModFolderInput,
@@ -94,27 +94,44 @@ impl Focus {
 pub enum UiMode {
     Explore,
     SelectFolder,
+    Frameworks,
+    LoadOrder,
+    Redmods,
+    Profiles,
+    Jobs,
 }
 
-impl UiMode {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for UiMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            UiMode::Explore => "Explore".to_string(),
-            UiMode::SelectFolder => "Select Folder".to_string(),
+            UiMode::Explore => write!(f, "Explore"),
+            UiMode::SelectFolder => write!(f, "Select Folder"),
+            UiMode::Frameworks => write!(f, "Frameworks"),
+            UiMode::LoadOrder => write!(f, "Load Order"),
+            UiMode::Redmods => write!(f, "REDmods"),
+            UiMode::Profiles => write!(f, "Profiles"),
+            UiMode::Jobs => write!(f, "Jobs"),
         }
     }
+}
 
+impl UiMode {
     pub fn from_string(s: &str) -> Option<UiMode> {
         match s {
             "Explore" => Some(UiMode::Explore),
             "Select Folder" => Some(UiMode::SelectFolder),
+            "Frameworks" => Some(UiMode::Frameworks),
+            "Load Order" => Some(UiMode::LoadOrder),
+            "REDmods" => Some(UiMode::Redmods),
+            "Profiles" => Some(UiMode::Profiles),
+            "Jobs" => Some(UiMode::Jobs),
             _ => None,
         }
     }
 
     pub fn get_available_targets(&self) -> Vec<String> {
         match self {
-            UiMode::Explore => vec![],
+            UiMode::Explore | UiMode::Frameworks | UiMode::LoadOrder | UiMode::Redmods | UiMode::Profiles | UiMode::Jobs => vec![],
             UiMode::SelectFolder => vec![
                 "Mod Folder".to_string(),
                 "Cyberpunk Folder".to_string(),
@@ -138,7 +155,17 @@ pub struct AppState {
     pub select_folder_form: Vec<String>,
     pub ui_mode: UiMode,
     pub file_list: StatefulList<(String, usize)>,
+    /// Every file of the mod folder, `file_list` only shows the ones matching `type_filter`
+    pub all_files: Vec<(String, usize)>,
+    pub type_filter: Option<ModType>,
+    /// Mods marked for a batch operation, they stay marked when the type filter hides them
+    pub marked: Vec<String>,
     pub mod_options: StatefulList<String>,
+    /// Archives of `archive/pc/mod` in the order they are loaded, with the mod that installed them
+    pub load_order: StatefulList<(String, Option<String>)>,
+    /// REDmods in deploy order
+    pub redmods: StatefulList<Redmod>,
+    pub profiles: StatefulList<Profile>,
     pub cursor_position: Option<usize>
 }
 
@@ -152,7 +179,13 @@ impl Default for AppState {
             select_folder_form: vec![String::new(), String::new()],
             ui_mode: UiMode::Explore,
             file_list: StatefulList::with_items(vec![]),
+            all_files: vec![],
+            type_filter: None,
+            marked: vec![],
             mod_options: StatefulList::with_items(mod_options_list),
+            load_order: StatefulList::with_items(vec![]),
+            redmods: StatefulList::with_items(vec![]),
+            profiles: StatefulList::with_items(vec![]),
             cursor_position: None
         }
     }
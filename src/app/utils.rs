@@ -1,12 +1,18 @@
-use std::{path::PathBuf, env::temp_dir, fs::{create_dir_all, File, remove_dir_all}};
+use std::{fmt, path::PathBuf};
 
-use compress_tools::{uncompress_archive, Ownership};
 use log::{info, error};
 use serde::{Serialize, Deserialize};
 use tui::widgets::ListState;
 use walkdir::WalkDir;
 
-use crate::constants::WORKING_DIR_NAME;
+use crate::constants::DEFAULT_EXTRACTION_CACHE_LIMIT_MB;
+use crate::io::IoEvent;
+use crate::io::conflicts::FileConflict;
+use crate::io::fomod::{Flags, FomodChoices, FomodConfig, InstallStep};
+use crate::io::load_order::LoadOrderPosition;
+use crate::io::profiles::Profile;
+use crate::io::mod_type::{Framework, ModClassification};
+use crate::io::status::ModInstallStatus;
 
 #[derive(Debug, Clone)]
 pub struct StatefulList<T> {
@@ -25,9 +31,7 @@ impl<T> StatefulList<T> {
     pub fn next(&mut self) {
         let i = match self.state.selected() {
             Some(i) => {
-                if self.items.is_empty() {
-                    0
-                } else if i >= self.items.len() - 1 {
+                if self.items.is_empty() || i >= self.items.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -63,7 +67,9 @@ impl<T> StatefulList<T> {
 pub enum ModOptions {
     Install,
     Uninstall,
-    Repair
+    Repair,
+    Disable,
+    Enable
 }
 
 impl ModOptions {
@@ -72,6 +78,8 @@ impl ModOptions {
             ModOptions::Install,
             ModOptions::Uninstall,
             ModOptions::Repair,
+            ModOptions::Disable,
+            ModOptions::Enable,
         ]
     }
 
@@ -85,28 +93,46 @@ impl ModOptions {
         .collect()
     }
 
-    pub fn to_string(&self) -> String {
-        match self {
-            ModOptions::Install => "Install".to_string(),
-            ModOptions::Uninstall => "Uninstall".to_string(),
-            ModOptions::Repair => "Repair".to_string(),
-        }
-    }
-
     pub fn get_option_from_string(option: &str) -> Option<ModOptions> {
         match option {
             "Install" => Some(ModOptions::Install),
             "Uninstall" => Some(ModOptions::Uninstall),
             "Repair" => Some(ModOptions::Repair),
+            "Disable" => Some(ModOptions::Disable),
+            "Enable" => Some(ModOptions::Enable),
             _ => None,
         }
     }
 }
 
+impl fmt::Display for ModOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModOptions::Install => write!(f, "Install"),
+            ModOptions::Uninstall => write!(f, "Uninstall"),
+            ModOptions::Repair => write!(f, "Repair"),
+            ModOptions::Disable => write!(f, "Disable"),
+            ModOptions::Enable => write!(f, "Enable"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ModPopup {
     pub mod_name: String,
-    pub mod_install_status: Option<bool>,
+    pub mod_install_status: Option<ModInstallStatus>,
+    pub conflicts: Vec<FileConflict>,
+    /// Folder of the archive picked by the user when it has several game roots
+    pub archive_root: Option<PathBuf>,
+    /// Options picked in the FOMOD installer of the mod
+    pub fomod_choices: Option<FomodChoices>,
+    /// Frameworks the mod needs that are not in the Cyberpunk folder
+    pub missing_frameworks: Vec<Framework>,
+    pub ignore_missing_frameworks: bool,
+    /// The user saw what the next install or uninstall changes and accepted it
+    pub plan_confirmed: bool,
+    /// The mod this framework is installed for, it is installed next
+    pub resume_after: Option<Box<ModPopup>>,
 }
 
 impl ModPopup {
@@ -114,6 +140,13 @@ impl ModPopup {
         Self {
             mod_name,
             mod_install_status: None,
+            conflicts: vec![],
+            archive_root: None,
+            fomod_choices: None,
+            missing_frameworks: vec![],
+            ignore_missing_frameworks: false,
+            plan_confirmed: false,
+            resume_after: None,
         }
     }
 
@@ -121,74 +154,204 @@ impl ModPopup {
         &self.mod_name
     }
 
-    pub fn get_mod_install_status(&self) -> Option<bool> {
-        self.mod_install_status
+    pub fn get_mod_install_status(&self) -> Option<&ModInstallStatus> {
+        self.mod_install_status.as_ref()
     }
 
-    pub fn set_mod_install_status(&mut self, status: bool) {
+    pub fn set_mod_install_status(&mut self, status: ModInstallStatus) {
         self.mod_install_status = Some(status);
     }
 }
 
+/// A question for the user with a fixed set of answers, each answer can trigger an IO event
+#[derive(Debug, Clone)]
+pub struct ChoicePopup {
+    pub title: String,
+    pub message: Vec<String>,
+    pub choices: StatefulList<(String, Option<IoEvent>)>,
+}
+
+impl ChoicePopup {
+    pub fn new(title: String, message: Vec<String>, choices: Vec<(String, Option<IoEvent>)>) -> Self {
+        let mut choices = StatefulList::with_items(choices);
+        choices.next();
+        Self {
+            title,
+            message,
+            choices,
+        }
+    }
+
+    /// The event associated with the highlighted answer
+    pub fn selected_event(&self) -> Option<IoEvent> {
+        self.choices
+            .state
+            .selected()
+            .and_then(|index| self.choices.items.get(index))
+            .and_then(|(_, event)| event.clone())
+    }
+}
+
+/// Walks the user through the steps of a FOMOD installer, one step at a time
+#[derive(Debug, Clone)]
+pub struct FomodWizard {
+    pub mod_name: String,
+    pub config: FomodConfig,
+    pub choices: FomodChoices,
+    pub cyberpunk_dir: PathBuf,
+    pub step_index: usize,
+    /// (group, plugin) of every option of the current step
+    pub options: StatefulList<(usize, usize)>,
+}
+
+impl FomodWizard {
+    /// Starts at the first visible step, `None` when the installer has nothing to pick
+    pub fn new(mod_name: String, config: FomodConfig, choices: FomodChoices, cyberpunk_dir: PathBuf) -> Option<Self> {
+        let step_index = config.next_visible_step(0, &choices, &cyberpunk_dir)?;
+        let mut fomod_wizard = Self {
+            mod_name,
+            config,
+            choices,
+            cyberpunk_dir,
+            step_index,
+            options: StatefulList::with_items(vec![]),
+        };
+        fomod_wizard.enter_step(step_index);
+        Some(fomod_wizard)
+    }
+
+    fn enter_step(&mut self, step_index: usize) {
+        self.step_index = step_index;
+        self.config.fill_defaults(step_index, &mut self.choices, &self.cyberpunk_dir);
+        let options = self.config.steps[step_index]
+            .groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group_index, group)| (0..group.plugins.len()).map(move |plugin_index| (group_index, plugin_index)))
+            .collect();
+        self.options = StatefulList::with_items(options);
+        self.options.next();
+    }
+
+    pub fn step(&self) -> &InstallStep {
+        &self.config.steps[self.step_index]
+    }
+
+    pub fn flags(&self) -> Flags {
+        self.config.flags_before(self.step_index, &self.choices, &self.cyberpunk_dir)
+    }
+
+    pub fn selected_option(&self) -> Option<(usize, usize)> {
+        self.options.state.selected().and_then(|index| self.options.items.get(index).copied())
+    }
+
+    pub fn is_selected(&self, group_index: usize, plugin_index: usize, flags: &Flags) -> bool {
+        let plugin = &self.step().groups[group_index].plugins[plugin_index];
+        self.config
+            .selected_plugins(self.step_index, group_index, &self.choices, flags, &self.cyberpunk_dir)
+            .contains(&plugin)
+    }
+
+    pub fn toggle_selected(&mut self) {
+        if let Some((group_index, plugin_index)) = self.selected_option() {
+            self.config.toggle(self.step_index, group_index, plugin_index, &mut self.choices, &self.cyberpunk_dir);
+        }
+    }
+
+    /// Groups of the current step that still need a choice
+    pub fn unmet_groups(&self) -> Vec<&str> {
+        self.config.unmet_groups(self.step_index, &self.choices, &self.cyberpunk_dir)
+    }
+
+    /// Move to the next visible step, `false` when this was the last one
+    pub fn next_step(&mut self) -> bool {
+        match self.config.next_visible_step(self.step_index + 1, &self.choices, &self.cyberpunk_dir) {
+            Some(step_index) => {
+                self.enter_step(step_index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move back to the previous visible step, `false` when this was the first one
+    pub fn previous_step(&mut self) -> bool {
+        match self.config.previous_visible_step(self.step_index, &self.choices, &self.cyberpunk_dir) {
+            Some(step_index) => {
+                self.enter_step(step_index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Position of the current step among the visible ones, and how many are visible
+    pub fn progress(&self) -> (usize, usize) {
+        let visible: Vec<usize> = (0..self.config.steps.len())
+            .filter(|index| {
+                let flags = self.config.flags_before(*index, &self.choices, &self.cyberpunk_dir);
+                self.config.is_step_visible(*index, &flags, &self.cyberpunk_dir)
+            })
+            .collect();
+        let position = visible.iter().position(|index| *index == self.step_index).unwrap_or_default();
+        (position + 1, visible.len())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Settings {
     pub cyberpunk_folder: Option<PathBuf>,
     pub mod_folder: Option<PathBuf>,
+    #[serde(default)]
+    pub new_archive_position: LoadOrderPosition,
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    #[serde(default)]
+    pub active_profile: Option<String>,
+    #[serde(default = "default_extraction_cache_limit_mb")]
+    pub extraction_cache_limit_mb: u64,
+}
+
+fn default_extraction_cache_limit_mb() -> u64 {
+    DEFAULT_EXTRACTION_CACHE_LIMIT_MB
 }
 
 pub fn log_help() {
     info!("Press <f> to select Mod and Cyberpunk Folders");
     info!("Use UP/DOWN to navigate the list");
     info!("Press <Enter> to select a file");
+    info!("Press <Space> to toggle an option of a mod installer");
+    info!("Press <t> to filter the list by mod type");
+    info!("Press <w> to see which frameworks are installed");
+    info!("Press <o> to change the archive load order, <+>/<-> to move an archive");
+    info!("Press <r> to manage REDmods, <Space> to enable or disable one");
+    info!("Press <p> to manage profiles, <i> to save the installed mods as one, <Enter> to switch");
     info!("Press <i> to enter input mode (Green Highlight)");
     info!("Press <Tab> to switch between input and submit button (Blue Highlight)");
+    info!("Press <x> to cancel a running install, uninstall or repair");
+    info!("Press <Space> to mark a mod, <a> to mark every shown mod, <Enter> to install, uninstall, enable, disable or verify the marked mods at once");
+    info!("Installs, uninstalls and profile switches show the files they change first, press <Enter> to go ahead or <Esc> to cancel");
+    info!("Press <j> to see queued, running, finished and failed background jobs");
     info!("Press <h> to see this help message again");
     info!("Press <Ctrl + s> to save settings");
     info!("Press <Esc> to exit input mode or close popup");
     info!("Press <q> to quit");
 }
 
-pub fn check_if_mod_is_valid(file_path: PathBuf) -> bool {
-    let mut is_valid = false;
+/// Decide from the classification made when the mod folder was loaded whether the archive is a mod
+pub fn check_if_mod_is_valid(file_path: PathBuf, classification: Option<&ModClassification>) -> bool {
     // make sure the file exists
     if !file_path.exists() {
         info!("{} does not exist", file_path.to_string_lossy());
         return false;
     }
-    let mut destination = temp_dir();
-    // make a cyberpunk_mod_manager directory in the temp directory
-    destination.push(WORKING_DIR_NAME);
-    // make a directory with the name of the file
-    destination.push(file_path.file_name().unwrap());
-    // create the directory
-    create_dir_all(&destination).unwrap();
-    // extract the zip file to the destination
-    let mut source = File::open(&file_path).unwrap();
-    uncompress_archive(&mut source, &destination, Ownership::Preserve).unwrap();
-    // check if the zip file contains any of the following folders
-    // archive, bin, engine, mods
-    // if it does, return true
-    // if the zip file has .ARCHIVE files, return true
-    // else return false
-    for entry in WalkDir::new(&destination) {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        if path.is_dir() {
-            let dir_name = path.file_name().unwrap().to_string_lossy();
-            if dir_name == "archive" || dir_name == "bin" || dir_name == "engine" || dir_name == "mods" || dir_name == "red4ext" || dir_name == "r6"{
-                is_valid = true;
-            }
-        }
-        if path.is_file() {
-            let file_name = path.file_name().unwrap().to_string_lossy();
-            if file_name.ends_with(".archive") {
-                is_valid = true;
-            }
+    match classification {
+        Some(classification) => classification.looks_like_mod,
+        None => {
+            error!("Could not read {}, reload the mod folder if it was added since", file_path.to_string_lossy());
+            false
         }
     }
-    // remove the directory
-    remove_dir_all(&destination).unwrap();
-    is_valid
 }
 
 pub fn check_if_cyberpunk_dir_is_valid(file_path: PathBuf) -> bool {
@@ -6,7 +6,28 @@ use tui::style::{
 
 pub const WORKING_DIR_NAME: &str = "cyberpunk_mod_manager";
 pub const SAVE_DIR_NAME: &str = "cyberpunk_mod_manager";
+/// Extracted mod archives are kept here between runs, inside the working directory
+pub const EXTRACTION_CACHE_DIR_NAME: &str = "extraction_cache";
+/// Default size limit of the extraction cache in megabytes
+pub const DEFAULT_EXTRACTION_CACHE_LIMIT_MB: u64 = 8192;
 pub const SAVE_FILE_NAME: &str = "cyberpunk_mod_manager.json";
+pub const GAME_DATA_DIR_NAME: &str = ".cyberpunk_mod_manager";
+pub const INSTALL_MANIFEST_FILE_NAME: &str = "install_manifest.json";
+pub const BACKUP_DIR_NAME: &str = "backups";
+pub const STAGING_DIR_NAME: &str = "staging";
+/// Originals of files that were changed after install, left for the user to restore by hand
+pub const KEPT_BACKUPS_DIR_NAME: &str = "kept_backups";
+pub const JOURNAL_FILE_NAME: &str = "journal.json";
+pub const FOMOD_CHOICES_FILE_NAME: &str = "fomod_choices.json";
+pub const DISABLED_REDMODS_DIR_NAME: &str = "disabled_redmods";
+pub const DISABLED_MODS_DIR_NAME: &str = "disabled";
+/// Appended to `.archive` files of disabled mods so the game skips them
+pub const DISABLED_ARCHIVE_EXTENSION: &str = "disabled";
+pub const REDMOD_INFO_FILE_NAME: &str = "info.json";
+/// Sets the order archives of `archive/pc/mod` are loaded in, since patch 2.0
+pub const MODLIST_FILE_NAME: &str = "modlist.txt";
+/// Folders found at the top of a Cyberpunk install, used to find where a mod archive maps onto it
+pub const GAME_ROOT_FOLDERS: [&str; 6] = ["archive", "bin", "r6", "red4ext", "engine", "mods"];
 pub const MIN_TERM_WIDTH: u16 = 110;
 pub const MIN_TERM_HEIGHT: u16 = 30;
 pub const APP_TITLE: &str = "Cyberpunk Mod Manager";
@@ -0,0 +1,48 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::Result;
+
+use crate::constants::{BACKUP_DIR_NAME, KEPT_BACKUPS_DIR_NAME};
+
+use super::manifest::InstallManifest;
+
+/// Originals of the game files a mod replaced, kept inside the Cyberpunk folder
+/// under the mod's name so they can be put back on uninstall
+pub struct BackupStore {
+    root: PathBuf,
+}
+
+impl BackupStore {
+    pub fn for_mod(cyberpunk_dir: &Path, mod_name: &str) -> Self {
+        Self {
+            root: InstallManifest::data_dir(cyberpunk_dir)
+                .join(BACKUP_DIR_NAME)
+                .join(mod_name),
+        }
+    }
+
+    /// Originals that could not be put back because the file was changed after install. They
+    /// outlive the mod so nothing the user had is lost
+    pub fn kept_for_mod(cyberpunk_dir: &Path, mod_name: &str) -> Self {
+        Self {
+            root: InstallManifest::data_dir(cyberpunk_dir)
+                .join(KEPT_BACKUPS_DIR_NAME)
+                .join(mod_name),
+        }
+    }
+
+    pub fn backup_path(&self, relative_path: &Path) -> PathBuf {
+        self.root.join(relative_path)
+    }
+
+    /// Drop the store once all of its files have been restored
+    pub fn clear(&self) -> Result<()> {
+        if self.root.exists() {
+            fs::remove_dir_all(&self.root)?;
+        }
+        Ok(())
+    }
+}
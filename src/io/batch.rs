@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use super::{
+    conflicts::ConflictResolution,
+    frameworks::required_frameworks,
+    manifest::{InstallManifest, InstalledMod},
+    mod_type::ModClassification,
+};
+
+/// What to do with every marked mod of the file list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchOperation {
+    Install(ConflictResolution),
+    Uninstall,
+    Enable,
+    Disable,
+    Verify,
+}
+
+impl BatchOperation {
+    pub fn to_str(&self) -> &str {
+        match self {
+            BatchOperation::Install(_) => "Install",
+            BatchOperation::Uninstall => "Uninstall",
+            BatchOperation::Enable => "Enable",
+            BatchOperation::Disable => "Disable",
+            BatchOperation::Verify => "Verify",
+        }
+    }
+
+    /// Whether the operation shows the files it changes before it runs, like it does for a single mod
+    pub fn is_planned(&self) -> bool {
+        matches!(self, BatchOperation::Install(_) | BatchOperation::Uninstall)
+    }
+
+    /// Why the operation has nothing to do for a mod, `installed_mod` is what the manifest has for it
+    pub fn skip_reason(&self, installed_mod: Option<&InstalledMod>) -> Option<&'static str> {
+        match (self, installed_mod) {
+            (BatchOperation::Install(_), Some(_)) => Some("already installed"),
+            (BatchOperation::Install(_), None) => None,
+            (_, None) => Some("not installed"),
+            (BatchOperation::Enable, Some(installed_mod)) if !installed_mod.disabled => Some("already enabled"),
+            (BatchOperation::Disable, Some(installed_mod)) if installed_mod.disabled => Some("already disabled"),
+            _ => None,
+        }
+    }
+
+    /// Whether the manifest shows the operation went through for a mod. Operations log why they
+    /// did nothing instead of failing, the manifest tells whether they did it
+    pub fn is_done(&self, manifest: &InstallManifest, mod_name: &str) -> bool {
+        let installed_mod = manifest.get(mod_name);
+        match self {
+            BatchOperation::Install(_) => installed_mod.is_some(),
+            BatchOperation::Uninstall => installed_mod.is_none(),
+            BatchOperation::Enable => installed_mod.is_some_and(|installed_mod| !installed_mod.disabled),
+            BatchOperation::Disable => installed_mod.is_some_and(|installed_mod| installed_mod.disabled),
+            BatchOperation::Verify => true,
+        }
+    }
+
+    /// Frameworks go first when mods are added, and last when they are taken out of the game
+    fn dependencies_first(&self) -> bool {
+        !matches!(self, BatchOperation::Uninstall | BatchOperation::Disable)
+    }
+}
+
+/// How one mod of a batch went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOutcome {
+    Done,
+    /// Nothing had to be done or the mod needs a decision only its own popup can ask for
+    Skipped(String),
+    Failed(String),
+}
+
+/// The outcome of every mod of a batch, in the order they ran
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub outcomes: Vec<(String, BatchOutcome)>,
+}
+
+impl BatchSummary {
+    pub fn add(&mut self, mod_name: String, outcome: BatchOutcome) {
+        self.outcomes.push((mod_name, outcome));
+    }
+
+    /// Mods that were not done, the file list keeps them marked so the batch can be run again
+    pub fn not_done(&self) -> Vec<String> {
+        self.outcomes
+            .iter()
+            .filter(|(_, outcome)| *outcome != BatchOutcome::Done)
+            .map(|(mod_name, _)| mod_name.clone())
+            .collect()
+    }
+
+    /// A line with the totals, then one line for every mod that was skipped or failed
+    pub fn lines(&self, operation: BatchOperation) -> Vec<String> {
+        let count = |wanted: fn(&BatchOutcome) -> bool| self.outcomes.iter().filter(|(_, outcome)| wanted(outcome)).count();
+        let mut lines = vec![format!(
+            "{} of {} mods: {} done, {} skipped, {} failed",
+            operation.to_str(),
+            self.outcomes.len(),
+            count(|outcome| *outcome == BatchOutcome::Done),
+            count(|outcome| matches!(outcome, BatchOutcome::Skipped(_))),
+            count(|outcome| matches!(outcome, BatchOutcome::Failed(_))),
+        )];
+        for (mod_name, outcome) in &self.outcomes {
+            match outcome {
+                BatchOutcome::Done => {}
+                BatchOutcome::Skipped(reason) => lines.push(format!("Skipped {}: {}", mod_name, reason)),
+                BatchOutcome::Failed(reason) => lines.push(format!("Failed {}: {}", mod_name, reason)),
+            }
+        }
+        lines
+    }
+}
+
+/// Order the mods of a batch so every mod comes after the frameworks of the batch it needs, or
+/// before them when they are taken out. Mods without dependencies on each other keep their order
+pub fn batch_order(
+    mod_names: &[String],
+    mod_types: &HashMap<String, ModClassification>,
+    operation: BatchOperation,
+) -> Vec<String> {
+    let classification = |mod_name: &String| mod_types.get(mod_name).cloned().unwrap_or_default();
+    let depends_on = |mod_name: &String, other: &String| {
+        let provided = classification(other).frameworks;
+        mod_name != other
+            && required_frameworks(&classification(mod_name))
+                .iter()
+                .any(|framework| provided.contains(framework))
+    };
+    let mut remaining = mod_names.to_vec();
+    let mut ordered = vec![];
+    while !remaining.is_empty() {
+        // a mod is ready once none of the remaining mods is one of its frameworks, cycles are broken in list order
+        let ready = remaining
+            .iter()
+            .position(|mod_name| !remaining.iter().any(|other| depends_on(mod_name, other)))
+            .unwrap_or(0);
+        ordered.push(remaining.remove(ready));
+    }
+    if !operation.dependencies_first() {
+        ordered.reverse();
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::mod_type::{Framework, ModType};
+
+    #[test]
+    fn should_skip_mods_the_operation_has_nothing_to_do_for() {
+        let mut installed_mod = InstalledMod::new("mod.zip".to_string(), "mod.zip".into(), String::new());
+        assert_eq!(BatchOperation::Install(ConflictResolution::Ask).skip_reason(Some(&installed_mod)), Some("already installed"));
+        assert_eq!(BatchOperation::Uninstall.skip_reason(None), Some("not installed"));
+        assert_eq!(BatchOperation::Enable.skip_reason(Some(&installed_mod)), Some("already enabled"));
+        assert_eq!(BatchOperation::Disable.skip_reason(Some(&installed_mod)), None);
+        installed_mod.disabled = true;
+        assert_eq!(BatchOperation::Disable.skip_reason(Some(&installed_mod)), Some("already disabled"));
+        assert_eq!(BatchOperation::Uninstall.skip_reason(Some(&installed_mod)), None);
+    }
+
+    #[test]
+    fn should_order_frameworks_around_their_dependents() {
+        let mut mod_types = HashMap::new();
+        mod_types.insert(
+            "tweaks.zip".to_string(),
+            ModClassification {
+                types: vec![ModType::TweakXl],
+                frameworks: vec![],
+                ..Default::default()
+            },
+        );
+        mod_types.insert(
+            "tweakxl.zip".to_string(),
+            ModClassification {
+                types: vec![ModType::Framework],
+                frameworks: vec![Framework::TweakXl],
+                ..Default::default()
+            },
+        );
+        mod_types.insert(
+            "red4ext.zip".to_string(),
+            ModClassification {
+                types: vec![ModType::Framework],
+                frameworks: vec![Framework::Red4ext],
+                ..Default::default()
+            },
+        );
+        let mod_names: Vec<String> = ["tweaks.zip", "other.zip", "tweakxl.zip", "red4ext.zip"]
+            .iter()
+            .map(|mod_name| mod_name.to_string())
+            .collect();
+
+        let install_order = batch_order(&mod_names, &mod_types, BatchOperation::Install(ConflictResolution::Ask));
+        assert_eq!(install_order, vec!["other.zip", "red4ext.zip", "tweakxl.zip", "tweaks.zip"]);
+        let uninstall_order = batch_order(&mod_names, &mod_types, BatchOperation::Uninstall);
+        assert_eq!(uninstall_order, vec!["tweaks.zip", "tweakxl.zip", "red4ext.zip", "other.zip"]);
+
+        let mut summary = BatchSummary::default();
+        summary.add("red4ext.zip".to_string(), BatchOutcome::Done);
+        summary.add("tweaks.zip".to_string(), BatchOutcome::Skipped("already installed".to_string()));
+        assert_eq!(
+            summary.lines(BatchOperation::Uninstall),
+            vec!["Uninstall of 2 mods: 1 done, 1 skipped, 0 failed", "Skipped tweaks.zip: already installed"]
+        );
+        assert_eq!(summary.not_done(), vec!["tweaks.zip"]);
+    }
+}
@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use super::manifest::OwnerIndex;
+
+/// A destination path of a mod that another installed mod already wrote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileConflict {
+    pub path: PathBuf,
+    pub owner: String,
+}
+
+/// What to do with files that are owned by other mods
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Stop and let the user decide
+    Ask,
+    /// Replace the files, the previous owner is recorded so it can be restored later
+    Overwrite,
+    /// Leave the other mods' files alone and install everything else
+    Skip,
+}
+
+/// Every destination of the mod that is already owned by another installed mod
+pub fn find_conflicts(owners: &OwnerIndex, mod_name: &str, destinations: &[PathBuf]) -> Vec<FileConflict> {
+    destinations
+        .iter()
+        .filter_map(|destination| {
+            owners
+                .owner_of(destination)
+                .filter(|owner| *owner != mod_name)
+                .map(|owner| FileConflict {
+                    path: destination.clone(),
+                    owner: owner.to_string(),
+                })
+        })
+        .collect()
+}
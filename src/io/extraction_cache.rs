@@ -0,0 +1,225 @@
+use std::{
+    env::temp_dir,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::UNIX_EPOCH,
+};
+
+use eyre::Result;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{EXTRACTION_CACHE_DIR_NAME, WORKING_DIR_NAME};
+
+use super::{
+    installer::{extract_archive, write_file_atomically},
+    manifest::{hash_file, unix_timestamp},
+    progress::ProgressHandle,
+};
+
+/// Numbers the extractions of this process so their partial folders never collide
+static NEXT_ATTEMPT: AtomicU64 = AtomicU64::new(0);
+
+/// What the cache knows about one extracted archive, saved next to its folder
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub archive_hash: String,
+    /// The archive it was extracted from, with its size and modification time so the hash of an
+    /// unchanged archive does not have to be computed again
+    pub archive_path: PathBuf,
+    pub archive_size: u64,
+    pub archive_modified: u64,
+    /// Bytes taken by the extracted files
+    pub size: u64,
+    /// Seconds since the unix epoch
+    pub last_used: u64,
+}
+
+/// Extracted mod archives kept in the working directory, keyed by the hash of the archive so
+/// opening, installing and repairing the same mod only extracts it once
+pub struct ExtractionCache {
+    root: PathBuf,
+    /// Entries are evicted, least recently used first, once they take more than this
+    max_size: u64,
+}
+
+impl ExtractionCache {
+    pub fn new(max_size: u64) -> Self {
+        Self {
+            root: temp_dir().join(WORKING_DIR_NAME).join(EXTRACTION_CACHE_DIR_NAME),
+            max_size,
+        }
+    }
+
+    fn entry_dir(&self, archive_hash: &str) -> PathBuf {
+        self.root.join(archive_hash)
+    }
+
+    fn entry_path(&self, archive_hash: &str) -> PathBuf {
+        self.root.join(format!("{}.json", archive_hash))
+    }
+
+    fn load_entry(&self, archive_hash: &str) -> Option<CacheEntry> {
+        let entry_json = fs::read_to_string(self.entry_path(archive_hash)).ok()?;
+        serde_json::from_str(&entry_json).ok()
+    }
+
+    fn save_entry(&self, entry: &CacheEntry) -> Result<()> {
+        write_file_atomically(&self.entry_path(&entry.archive_hash), serde_json::to_string_pretty(entry)?)
+    }
+
+    /// Every complete entry, the folder of an entry is only recorded once its extraction finished
+    pub fn entries(&self) -> Vec<CacheEntry> {
+        let Ok(dir_entries) = fs::read_dir(&self.root) else {
+            return vec![];
+        };
+        dir_entries
+            .flatten()
+            .filter_map(|dir_entry| {
+                let file_name = dir_entry.file_name().to_string_lossy().to_string();
+                let archive_hash = file_name.strip_suffix(".json")?;
+                self.load_entry(archive_hash)
+            })
+            .collect()
+    }
+
+    /// The hash of a mod archive, taken from the cache when the archive did not change since it was extracted
+    pub fn archive_hash(&self, mod_path: &Path) -> Result<String> {
+        let (archive_size, archive_modified) = file_stamp(mod_path)?;
+        let cached = self.entries().into_iter().find(|entry| {
+            entry.archive_path == mod_path && entry.archive_size == archive_size && entry.archive_modified == archive_modified
+        });
+        match cached {
+            Some(entry) => Ok(entry.archive_hash),
+            None => hash_file(mod_path),
+        }
+    }
+
+    /// The folder holding the extracted files of a mod archive, extracting it first if it is not cached
+    pub fn extract(&self, mod_path: &Path, archive_hash: &str, progress: &ProgressHandle) -> Result<PathBuf> {
+        let entry_dir = self.entry_dir(archive_hash);
+        if let Some(mut entry) = self.load_entry(archive_hash).filter(|_| entry_dir.is_dir()) {
+            debug!("🚀 Using the cached extraction of {}", mod_path.to_string_lossy());
+            entry.archive_path = mod_path.to_path_buf();
+            (entry.archive_size, entry.archive_modified) = file_stamp(mod_path)?;
+            entry.last_used = unix_timestamp();
+            self.save_entry(&entry)?;
+            return Ok(entry_dir);
+        }
+        info!("🚀 Extracting {}", mod_path.file_name().unwrap_or_default().to_string_lossy());
+        // extract next to the entry and rename it once done, so a half extracted archive is never used.
+        // Every attempt gets a folder of its own, another job may be extracting the same archive
+        let attempt = NEXT_ATTEMPT.fetch_add(1, Ordering::SeqCst);
+        let partial_dir = self.root.join(format!("{}.{}.{}.partial", archive_hash, process::id(), attempt));
+        fs::create_dir_all(&partial_dir)?;
+        let size = match extract_archive(mod_path, &partial_dir, progress) {
+            Ok(size) => size,
+            Err(err) => {
+                fs::remove_dir_all(&partial_dir)?;
+                return Err(err);
+            }
+        };
+        // entry folders are only renamed into place once complete, one left by another attempt can be used
+        if entry_dir.is_dir() {
+            fs::remove_dir_all(&partial_dir)?;
+        } else {
+            fs::rename(&partial_dir, &entry_dir)?;
+        }
+        let (archive_size, archive_modified) = file_stamp(mod_path)?;
+        self.save_entry(&CacheEntry {
+            archive_hash: archive_hash.to_string(),
+            archive_path: mod_path.to_path_buf(),
+            archive_size,
+            archive_modified,
+            size,
+            last_used: unix_timestamp(),
+        })?;
+        if let Err(err) = self.evict(archive_hash) {
+            warn!("⚠️ Could not clean up the extraction cache: {:?}", err);
+        }
+        Ok(entry_dir)
+    }
+
+    /// Remove extractions that never finished, left behind when the manager was closed during one
+    pub fn remove_partial_extractions(&self) -> Result<()> {
+        let Ok(dir_entries) = fs::read_dir(&self.root) else {
+            return Ok(());
+        };
+        for dir_entry in dir_entries.flatten() {
+            if dir_entry.file_name().to_string_lossy().ends_with(".partial") {
+                fs::remove_dir_all(dir_entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop least recently used entries until the cache fits its size limit again, `keep` is never dropped
+    fn evict(&self, keep: &str) -> Result<()> {
+        for entry in entries_to_evict(self.entries(), self.max_size, keep) {
+            debug!("🚀 Evicting {} from the extraction cache", entry.archive_path.to_string_lossy());
+            fs::remove_file(self.entry_path(&entry.archive_hash))?;
+            let entry_dir = self.entry_dir(&entry.archive_hash);
+            if entry_dir.exists() {
+                fs::remove_dir_all(entry_dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The entries to remove, least recently used first, for the rest to take at most `max_size` bytes
+pub fn entries_to_evict(mut entries: Vec<CacheEntry>, max_size: u64, keep: &str) -> Vec<CacheEntry> {
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+    entries.sort_by_key(|entry| entry.last_used);
+    entries
+        .into_iter()
+        .filter(|entry| entry.archive_hash != keep)
+        .take_while(|entry| {
+            let over_limit = total > max_size;
+            total -= entry.size;
+            over_limit
+        })
+        .collect()
+}
+
+fn file_stamp(path: &Path) -> Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    Ok((metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(archive_hash: &str, size: u64, last_used: u64) -> CacheEntry {
+        CacheEntry {
+            archive_hash: archive_hash.to_string(),
+            archive_path: PathBuf::from(format!("{}.zip", archive_hash)),
+            archive_size: size,
+            archive_modified: 0,
+            size,
+            last_used,
+        }
+    }
+
+    #[test]
+    fn should_evict_least_recently_used_first() {
+        let entries = vec![entry("new", 40, 3), entry("old", 30, 1), entry("middle", 50, 2)];
+        let evicted: Vec<String> = entries_to_evict(entries.clone(), 100, "new")
+            .into_iter()
+            .map(|entry| entry.archive_hash)
+            .collect();
+        assert_eq!(evicted, vec!["old".to_string()]);
+        // the entry just extracted stays even when it alone is over the limit
+        let evicted = entries_to_evict(entries.clone(), 10, "new");
+        assert_eq!(evicted.len(), 2);
+        assert!(entries_to_evict(entries, 120, "new").is_empty());
+    }
+}
@@ -0,0 +1,856 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Result};
+use log::warn;
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::constants::FOMOD_CHOICES_FILE_NAME;
+
+use super::{
+    installer::{write_file_atomically, ModFile},
+    manifest::InstallManifest,
+    paths::{normalize_archive_path, normalize_relative_path, to_portable_string, CaseResolver},
+};
+
+/// Flags set by the selected plugins, an unset flag compares equal to an empty value
+pub type Flags = HashMap<String, String>;
+
+/// How many plugins of a group can be picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupType {
+    SelectExactlyOne,
+    SelectAtMostOne,
+    SelectAtLeastOne,
+    SelectAll,
+    SelectAny,
+}
+
+impl GroupType {
+    fn parse(value: &str) -> Self {
+        match value {
+            "SelectExactlyOne" => GroupType::SelectExactlyOne,
+            "SelectAtMostOne" => GroupType::SelectAtMostOne,
+            "SelectAtLeastOne" => GroupType::SelectAtLeastOne,
+            "SelectAll" => GroupType::SelectAll,
+            _ => GroupType::SelectAny,
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            GroupType::SelectExactlyOne => "Select one",
+            GroupType::SelectAtMostOne => "Select at most one",
+            GroupType::SelectAtLeastOne => "Select at least one",
+            GroupType::SelectAll => "All required",
+            GroupType::SelectAny => "Select any",
+        }
+    }
+
+    /// Groups shown as radio buttons instead of checkboxes
+    pub fn is_single(&self) -> bool {
+        matches!(self, GroupType::SelectExactlyOne | GroupType::SelectAtMostOne)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginType {
+    Required,
+    Recommended,
+    Optional,
+    CouldBeUsable,
+    NotUsable,
+}
+
+impl PluginType {
+    fn parse(value: &str) -> Self {
+        match value {
+            "Required" => PluginType::Required,
+            "Recommended" => PluginType::Recommended,
+            "CouldBeUsable" => PluginType::CouldBeUsable,
+            "NotUsable" => PluginType::NotUsable,
+            _ => PluginType::Optional,
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            PluginType::Required => "Required",
+            PluginType::Recommended => "Recommended",
+            PluginType::Optional => "Optional",
+            PluginType::CouldBeUsable => "Could be usable",
+            PluginType::NotUsable => "Not usable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Flag { name: String, value: String },
+    /// `Missing` is met when the file is absent from the Cyberpunk folder, any other state when it exists
+    File { path: PathBuf, state: String },
+    Nested(Dependencies),
+}
+
+/// A set of conditions joined with `And` or `Or`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependencies {
+    pub require_all: bool,
+    pub conditions: Vec<Condition>,
+}
+
+impl Dependencies {
+    pub fn is_met(&self, flags: &Flags, cyberpunk_dir: &Path) -> bool {
+        let mut results = self.conditions.iter().map(|condition| match condition {
+            Condition::Flag { name, value } => flags.get(name).map(String::as_str).unwrap_or_default() == value,
+            Condition::File { path, state } => cyberpunk_dir.join(path).exists() != (state == "Missing"),
+            Condition::Nested(dependencies) => dependencies.is_met(flags, cyberpunk_dir),
+        });
+        if self.require_all {
+            results.all(|met| met)
+        } else {
+            self.conditions.is_empty() || results.any(|met| met)
+        }
+    }
+}
+
+/// A `file` or `folder` entry, paths are relative to the folder holding `fomod/`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FomodFile {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub is_folder: bool,
+    /// Files with a higher priority win when destinations collide
+    pub priority: i32,
+    /// Installed even when the plugin is not selected
+    pub always_install: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDescriptor {
+    pub default: PluginType,
+    pub patterns: Vec<(Dependencies, PluginType)>,
+}
+
+impl TypeDescriptor {
+    pub fn plugin_type(&self, flags: &Flags, cyberpunk_dir: &Path) -> PluginType {
+        self.patterns
+            .iter()
+            .find(|(dependencies, _)| dependencies.is_met(flags, cyberpunk_dir))
+            .map(|(_, plugin_type)| *plugin_type)
+            .unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    pub name: String,
+    pub description: String,
+    pub files: Vec<FomodFile>,
+    pub condition_flags: Vec<(String, String)>,
+    pub type_descriptor: TypeDescriptor,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginGroup {
+    pub name: String,
+    pub group_type: GroupType,
+    pub plugins: Vec<Plugin>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallStep {
+    pub name: String,
+    pub visible: Option<Dependencies>,
+    pub groups: Vec<PluginGroup>,
+}
+
+/// Files installed when the flags set by the selected plugins match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionalInstall {
+    pub dependencies: Dependencies,
+    pub files: Vec<FomodFile>,
+}
+
+/// The plugins picked in one group, stored by name so the choices survive a new version of the mod
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FomodSelection {
+    pub step: String,
+    pub group: String,
+    pub plugins: Vec<String>,
+}
+
+/// Everything the user picked in the installer of a mod
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct FomodChoices {
+    pub selections: Vec<FomodSelection>,
+}
+
+impl FomodChoices {
+    pub fn get(&self, step: &str, group: &str) -> Option<&Vec<String>> {
+        self.selections
+            .iter()
+            .find(|selection| selection.step == step && selection.group == group)
+            .map(|selection| &selection.plugins)
+    }
+
+    pub fn set(&mut self, step: &str, group: &str, plugins: Vec<String>) {
+        match self
+            .selections
+            .iter_mut()
+            .find(|selection| selection.step == step && selection.group == group)
+        {
+            Some(selection) => selection.plugins = plugins,
+            None => self.selections.push(FomodSelection {
+                step: step.to_string(),
+                group: group.to_string(),
+                plugins,
+            }),
+        }
+    }
+}
+
+/// The last choices made for every mod, kept next to the install manifest so a reinstall starts from them
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct FomodChoiceStore {
+    pub mods: BTreeMap<String, FomodChoices>,
+}
+
+impl FomodChoiceStore {
+    pub fn store_path(cyberpunk_dir: &Path) -> PathBuf {
+        InstallManifest::data_dir(cyberpunk_dir).join(FOMOD_CHOICES_FILE_NAME)
+    }
+
+    pub fn load(cyberpunk_dir: &Path) -> Result<Self> {
+        let store_path = Self::store_path(cyberpunk_dir);
+        if !store_path.exists() {
+            return Ok(Self::default());
+        }
+        let store_json = fs::read_to_string(store_path)?;
+        Ok(serde_json::from_str(&store_json)?)
+    }
+
+    pub fn save(&self, cyberpunk_dir: &Path) -> Result<()> {
+        fs::create_dir_all(InstallManifest::data_dir(cyberpunk_dir))?;
+        write_file_atomically(&Self::store_path(cyberpunk_dir), serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Whether a path is the `fomod/ModuleConfig.xml` of a FOMOD installer
+pub fn is_fomod_config(path: &Path) -> bool {
+    path.file_name().unwrap_or_default().eq_ignore_ascii_case("moduleconfig.xml")
+        && path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .unwrap_or_default()
+            .eq_ignore_ascii_case("fomod")
+}
+
+/// A parsed `fomod/ModuleConfig.xml`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FomodConfig {
+    pub module_name: String,
+    /// Folder holding `fomod/`, relative to the extraction root
+    pub root: PathBuf,
+    pub required_files: Vec<FomodFile>,
+    pub steps: Vec<InstallStep>,
+    pub conditional_installs: Vec<ConditionalInstall>,
+}
+
+fn children<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| child.has_tag_name(name))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> Option<Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+fn text_of(node: Option<Node>) -> String {
+    node.and_then(|node| node.text()).unwrap_or_default().trim().to_string()
+}
+
+/// Steps, groups and plugins are sorted by name unless the config asks for its own order
+fn sort_by_order<T>(items: &mut [T], order: Option<&str>, name: fn(&T) -> &str) {
+    match order.unwrap_or("Ascending") {
+        "Explicit" => {}
+        "Descending" => items.sort_by(|a, b| name(b).cmp(name(a))),
+        _ => items.sort_by(|a, b| name(a).cmp(name(b))),
+    }
+}
+
+fn parse_dependencies(node: Node) -> Dependencies {
+    let conditions = node
+        .children()
+        .filter(|child| child.is_element())
+        .filter_map(|child| match child.tag_name().name() {
+            "flagDependency" => Some(Condition::Flag {
+                name: child.attribute("flag").unwrap_or_default().to_string(),
+                value: child.attribute("value").unwrap_or_default().to_string(),
+            }),
+            "fileDependency" => {
+                let file = child.attribute("file").unwrap_or_default();
+                let path = normalize_archive_path(file);
+                if path.is_none() {
+                    warn!("⚠️ Ignoring the FOMOD condition on {}, it is outside of the Cyberpunk folder", file);
+                }
+                path.map(|path| Condition::File {
+                    path,
+                    state: child.attribute("state").unwrap_or_default().to_string(),
+                })
+            }
+            "dependencies" => Some(Condition::Nested(parse_dependencies(child))),
+            // game and script extender versions don't apply to Cyberpunk
+            _ => None,
+        })
+        .collect();
+    Dependencies {
+        require_all: node.attribute("operator") != Some("Or"),
+        conditions,
+    }
+}
+
+fn parse_files(node: Option<Node>) -> Vec<FomodFile> {
+    let node = match node {
+        Some(node) => node,
+        None => return vec![],
+    };
+    node.children()
+        .filter(|child| child.has_tag_name("file") || child.has_tag_name("folder"))
+        .filter_map(|child| {
+            let source_attribute = child.attribute("source").unwrap_or_default();
+            let is_folder = child.has_tag_name("folder");
+            let paths = normalize_archive_path(source_attribute).and_then(|source| {
+                let destination = match child.attribute("destination") {
+                    Some(destination) => normalize_archive_path(destination)?,
+                    // a file without destination keeps its path, a folder is merged into the game folder
+                    None if !is_folder => source.clone(),
+                    None => PathBuf::new(),
+                };
+                Some((source, destination))
+            });
+            let Some((source, destination)) = paths else {
+                warn!("⚠️ Skipping {} listed in the FOMOD installer, it leaves the mod or Cyberpunk folder", source_attribute);
+                return None;
+            };
+            Some(FomodFile {
+                source,
+                destination,
+                is_folder,
+                priority: child.attribute("priority").and_then(|priority| priority.parse().ok()).unwrap_or(0),
+                always_install: child.attribute("alwaysInstall") == Some("true"),
+            })
+        })
+        .collect()
+}
+
+fn parse_type_descriptor(node: Option<Node>) -> TypeDescriptor {
+    let mut type_descriptor = TypeDescriptor {
+        default: PluginType::Optional,
+        patterns: vec![],
+    };
+    let node = match node {
+        Some(node) => node,
+        None => return type_descriptor,
+    };
+    if let Some(plugin_type) = child(node, "type") {
+        type_descriptor.default = PluginType::parse(plugin_type.attribute("name").unwrap_or_default());
+    } else if let Some(dependency_type) = child(node, "dependencyType") {
+        if let Some(default_type) = child(dependency_type, "defaultType") {
+            type_descriptor.default = PluginType::parse(default_type.attribute("name").unwrap_or_default());
+        }
+        if let Some(patterns) = child(dependency_type, "patterns") {
+            for pattern in children(patterns, "pattern") {
+                if let (Some(dependencies), Some(plugin_type)) = (child(pattern, "dependencies"), child(pattern, "type")) {
+                    type_descriptor.patterns.push((
+                        parse_dependencies(dependencies),
+                        PluginType::parse(plugin_type.attribute("name").unwrap_or_default()),
+                    ));
+                }
+            }
+        }
+    }
+    type_descriptor
+}
+
+fn parse_plugin(node: Node) -> Plugin {
+    let condition_flags = child(node, "conditionFlags")
+        .map(|flags| {
+            children(flags, "flag")
+                .map(|flag| (flag.attribute("name").unwrap_or_default().to_string(), text_of(Some(flag))))
+                .collect()
+        })
+        .unwrap_or_default();
+    Plugin {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        description: text_of(child(node, "description")),
+        files: parse_files(child(node, "files")),
+        condition_flags,
+        type_descriptor: parse_type_descriptor(child(node, "typeDescriptor")),
+    }
+}
+
+fn parse_group(node: Node) -> PluginGroup {
+    let mut plugins: Vec<Plugin> = vec![];
+    if let Some(plugins_node) = child(node, "plugins") {
+        plugins = children(plugins_node, "plugin").map(parse_plugin).collect();
+        sort_by_order(&mut plugins, plugins_node.attribute("order"), |plugin| &plugin.name);
+    }
+    PluginGroup {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        group_type: GroupType::parse(node.attribute("type").unwrap_or_default()),
+        plugins,
+    }
+}
+
+fn parse_step(node: Node) -> InstallStep {
+    let mut groups: Vec<PluginGroup> = vec![];
+    if let Some(groups_node) = child(node, "optionalFileGroups") {
+        groups = children(groups_node, "group").map(parse_group).collect();
+        sort_by_order(&mut groups, groups_node.attribute("order"), |group| &group.name);
+    }
+    InstallStep {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        visible: child(node, "visible").map(parse_dependencies),
+        groups,
+    }
+}
+
+/// ModuleConfig.xml is often saved as UTF-16 by the tools that generate it
+fn decode_xml(bytes: &[u8]) -> String {
+    let utf16 = |chunks: std::slice::ChunksExact<u8>, little_endian: bool| {
+        let units: Vec<u16> = chunks
+            .map(|pair| if little_endian { u16::from_le_bytes([pair[0], pair[1]]) } else { u16::from_be_bytes([pair[0], pair[1]]) })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest.chunks_exact(2), true),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest.chunks_exact(2), false),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).to_string(),
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+impl FomodConfig {
+    pub fn parse(xml: &str, root: PathBuf) -> Result<Self> {
+        let document = Document::parse(xml).map_err(|err| eyre!("Invalid ModuleConfig.xml: {}", err))?;
+        let config = document.root_element();
+        let mut steps: Vec<InstallStep> = vec![];
+        if let Some(steps_node) = child(config, "installSteps") {
+            steps = children(steps_node, "installStep").map(parse_step).collect();
+            sort_by_order(&mut steps, steps_node.attribute("order"), |step| &step.name);
+        }
+        let conditional_installs = child(config, "conditionalFileInstalls")
+            .and_then(|node| child(node, "patterns"))
+            .map(|patterns| {
+                children(patterns, "pattern")
+                    .map(|pattern| ConditionalInstall {
+                        dependencies: child(pattern, "dependencies")
+                            .map(parse_dependencies)
+                            .unwrap_or(Dependencies { require_all: true, conditions: vec![] }),
+                        files: parse_files(child(pattern, "files")),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            module_name: text_of(child(config, "moduleName")),
+            root,
+            required_files: parse_files(child(config, "requiredInstallFiles")),
+            steps,
+            conditional_installs,
+        })
+    }
+
+    /// Look for `fomod/ModuleConfig.xml` anywhere in an extracted mod
+    pub fn find(temp_mod_path: &Path) -> Result<Option<Self>> {
+        let config_path = WalkDir::new(temp_mod_path)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .filter(|path| is_fomod_config(path))
+            .min_by_key(|path| path.components().count());
+        let config_path = match config_path {
+            Some(config_path) => config_path,
+            None => return Ok(None),
+        };
+        let fomod_dir = config_path.parent().unwrap_or(temp_mod_path);
+        let root = fomod_dir.parent().unwrap_or(temp_mod_path).strip_prefix(temp_mod_path)?;
+        let xml = decode_xml(&fs::read(&config_path)?);
+        let root = normalize_relative_path(root).ok_or_else(|| eyre!("{} is not inside the archive", root.to_string_lossy()))?;
+        Ok(Some(Self::parse(&xml, root)?))
+    }
+
+    pub fn display_name(&self) -> &str {
+        if self.module_name.is_empty() {
+            "FOMOD installer"
+        } else {
+            &self.module_name
+        }
+    }
+
+    /// Flags set by the selected plugins of the visible steps before `step_index`
+    pub fn flags_before(&self, step_index: usize, choices: &FomodChoices, cyberpunk_dir: &Path) -> Flags {
+        let mut flags = Flags::new();
+        for index in 0..step_index.min(self.steps.len()) {
+            if !self.is_step_visible(index, &flags, cyberpunk_dir) {
+                continue;
+            }
+            for group_index in 0..self.steps[index].groups.len() {
+                for plugin in self.selected_plugins(index, group_index, choices, &flags, cyberpunk_dir) {
+                    for (name, value) in &plugin.condition_flags {
+                        flags.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        flags
+    }
+
+    pub fn is_step_visible(&self, step_index: usize, flags: &Flags, cyberpunk_dir: &Path) -> bool {
+        self.steps[step_index]
+            .visible
+            .as_ref()
+            .map(|visible| visible.is_met(flags, cyberpunk_dir))
+            .unwrap_or(true)
+    }
+
+    /// The first visible step at or after `start`
+    pub fn next_visible_step(&self, start: usize, choices: &FomodChoices, cyberpunk_dir: &Path) -> Option<usize> {
+        (start..self.steps.len()).find(|index| {
+            let flags = self.flags_before(*index, choices, cyberpunk_dir);
+            self.is_step_visible(*index, &flags, cyberpunk_dir)
+        })
+    }
+
+    /// The last visible step before `end`
+    pub fn previous_visible_step(&self, end: usize, choices: &FomodChoices, cyberpunk_dir: &Path) -> Option<usize> {
+        (0..end.min(self.steps.len())).rev().find(|index| {
+            let flags = self.flags_before(*index, choices, cyberpunk_dir);
+            self.is_step_visible(*index, &flags, cyberpunk_dir)
+        })
+    }
+
+    fn default_selection(&self, group: &PluginGroup, flags: &Flags, cyberpunk_dir: &Path) -> Vec<String> {
+        let usable: Vec<(&Plugin, PluginType)> = group
+            .plugins
+            .iter()
+            .map(|plugin| (plugin, plugin.type_descriptor.plugin_type(flags, cyberpunk_dir)))
+            .filter(|(_, plugin_type)| *plugin_type != PluginType::NotUsable)
+            .collect();
+        let mut selection: Vec<String> = usable
+            .iter()
+            .filter(|(_, plugin_type)| matches!(plugin_type, PluginType::Required | PluginType::Recommended))
+            .map(|(plugin, _)| plugin.name.clone())
+            .collect();
+        if group.group_type.is_single() {
+            selection.truncate(1);
+        }
+        if selection.is_empty() && group.group_type == GroupType::SelectExactlyOne {
+            selection.extend(usable.first().map(|(plugin, _)| plugin.name.clone()));
+        }
+        selection
+    }
+
+    /// The plugin names picked in a group, falling back to the defaults of the config
+    pub fn selection(&self, step_index: usize, group_index: usize, choices: &FomodChoices, flags: &Flags, cyberpunk_dir: &Path) -> Vec<String> {
+        let step = &self.steps[step_index];
+        let group = &step.groups[group_index];
+        match choices.get(&step.name, &group.name) {
+            Some(plugins) => plugins.clone(),
+            None => self.default_selection(group, flags, cyberpunk_dir),
+        }
+    }
+
+    /// The plugins of a group that get installed, required plugins are always part of it
+    pub fn selected_plugins(&self, step_index: usize, group_index: usize, choices: &FomodChoices, flags: &Flags, cyberpunk_dir: &Path) -> Vec<&Plugin> {
+        let group = &self.steps[step_index].groups[group_index];
+        let selection = self.selection(step_index, group_index, choices, flags, cyberpunk_dir);
+        group
+            .plugins
+            .iter()
+            .filter(|plugin| {
+                let plugin_type = plugin.type_descriptor.plugin_type(flags, cyberpunk_dir);
+                plugin_type != PluginType::NotUsable
+                    && (group.group_type == GroupType::SelectAll
+                        || plugin_type == PluginType::Required
+                        || selection.contains(&plugin.name))
+            })
+            .collect()
+    }
+
+    /// Record the current selection of every group of a step so later steps see stable flags
+    pub fn fill_defaults(&self, step_index: usize, choices: &mut FomodChoices, cyberpunk_dir: &Path) {
+        let flags = self.flags_before(step_index, choices, cyberpunk_dir);
+        let step = &self.steps[step_index];
+        for (group_index, group) in step.groups.iter().enumerate() {
+            if choices.get(&step.name, &group.name).is_none() {
+                let selection = self.selection(step_index, group_index, choices, &flags, cyberpunk_dir);
+                choices.set(&step.name, &group.name, selection);
+            }
+        }
+    }
+
+    /// Select or deselect a plugin following the rules of its group
+    pub fn toggle(&self, step_index: usize, group_index: usize, plugin_index: usize, choices: &mut FomodChoices, cyberpunk_dir: &Path) {
+        let flags = self.flags_before(step_index, choices, cyberpunk_dir);
+        let step = &self.steps[step_index];
+        let group = &step.groups[group_index];
+        let plugin = &group.plugins[plugin_index];
+        let plugin_type = plugin.type_descriptor.plugin_type(&flags, cyberpunk_dir);
+        if group.group_type == GroupType::SelectAll || matches!(plugin_type, PluginType::Required | PluginType::NotUsable) {
+            return;
+        }
+        let mut selection = self.selection(step_index, group_index, choices, &flags, cyberpunk_dir);
+        let is_selected = selection.contains(&plugin.name);
+        match group.group_type {
+            GroupType::SelectExactlyOne => selection = vec![plugin.name.clone()],
+            GroupType::SelectAtMostOne if is_selected => selection.clear(),
+            GroupType::SelectAtMostOne => selection = vec![plugin.name.clone()],
+            _ if is_selected => selection.retain(|name| *name != plugin.name),
+            _ => selection.push(plugin.name.clone()),
+        }
+        choices.set(&step.name, &group.name, selection);
+    }
+
+    /// Groups of a step whose selection breaks the rules of their type
+    pub fn unmet_groups(&self, step_index: usize, choices: &FomodChoices, cyberpunk_dir: &Path) -> Vec<&str> {
+        let flags = self.flags_before(step_index, choices, cyberpunk_dir);
+        self.steps[step_index]
+            .groups
+            .iter()
+            .enumerate()
+            .filter(|(group_index, group)| {
+                let selected = self.selected_plugins(step_index, *group_index, choices, &flags, cyberpunk_dir).len();
+                match group.group_type {
+                    GroupType::SelectExactlyOne => selected != 1 && !group.plugins.is_empty(),
+                    GroupType::SelectAtLeastOne => selected == 0 && !group.plugins.is_empty(),
+                    _ => false,
+                }
+            })
+            .map(|(_, group)| group.name.as_str())
+            .collect()
+    }
+
+    /// Every `file` and `folder` entry installed with these choices, lowest priority first
+    pub fn selected_files(&self, choices: &FomodChoices, cyberpunk_dir: &Path) -> Vec<FomodFile> {
+        let mut files = self.required_files.clone();
+        let mut flags = Flags::new();
+        for (step_index, step) in self.steps.iter().enumerate() {
+            if !self.is_step_visible(step_index, &flags, cyberpunk_dir) {
+                continue;
+            }
+            let mut step_flags = vec![];
+            for (group_index, group) in step.groups.iter().enumerate() {
+                let selected = self.selected_plugins(step_index, group_index, choices, &flags, cyberpunk_dir);
+                for plugin in &group.plugins {
+                    if selected.contains(&plugin) {
+                        files.extend(plugin.files.iter().cloned());
+                        step_flags.extend(plugin.condition_flags.iter().cloned());
+                    } else {
+                        files.extend(plugin.files.iter().filter(|file| file.always_install).cloned());
+                    }
+                }
+            }
+            flags.extend(step_flags);
+        }
+        for conditional_install in &self.conditional_installs {
+            if conditional_install.dependencies.is_met(&flags, cyberpunk_dir) {
+                files.extend(conditional_install.files.iter().cloned());
+            }
+        }
+        // stable, so entries with the same priority keep the order of the config
+        files.sort_by_key(|file| file.priority);
+        files
+    }
+
+    /// Map the selected files onto the Cyberpunk folder, later entries win when destinations collide
+    pub fn mod_files(&self, temp_mod_path: &Path, choices: &FomodChoices, cyberpunk_dir: &Path) -> Result<Vec<ModFile>> {
+        let root = temp_mod_path.join(&self.root);
+        // the config is written on Windows and rarely matches the casing of the archive
+        let mut resolver = CaseResolver::new(&root);
+        let mut mod_files: Vec<ModFile> = vec![];
+        let mut index_by_destination: HashMap<String, usize> = HashMap::new();
+        let mut add = |mod_file: ModFile| {
+            let key = to_portable_string(&mod_file.destination).to_lowercase();
+            match index_by_destination.get(&key) {
+                Some(index) => mod_files[*index] = mod_file,
+                None => {
+                    index_by_destination.insert(key, mod_files.len());
+                    mod_files.push(mod_file);
+                }
+            }
+        };
+        for fomod_file in self.selected_files(choices, cyberpunk_dir) {
+            let source = root.join(resolver.resolve(&fomod_file.source));
+            if fomod_file.is_folder && source.is_dir() {
+                let mut sources: Vec<PathBuf> = WalkDir::new(&source)
+                    .into_iter()
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().is_file())
+                    .map(|entry| entry.into_path())
+                    .collect();
+                sources.sort();
+                for file_source in sources {
+                    let Some(relative_path) = normalize_relative_path(file_source.strip_prefix(&source)?) else {
+                        warn!("⚠️ Skipping {}, it would be installed outside of the Cyberpunk folder", file_source.to_string_lossy());
+                        continue;
+                    };
+                    add(ModFile {
+                        source: file_source,
+                        destination: fomod_file.destination.join(relative_path),
+                    });
+                }
+            } else if !fomod_file.is_folder && source.is_file() {
+                add(ModFile {
+                    source,
+                    destination: fomod_file.destination,
+                });
+            } else {
+                warn!("⚠️ {} listed in the FOMOD installer is missing from the archive", to_portable_string(&fomod_file.source));
+            }
+        }
+        Ok(mod_files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE_CONFIG: &str = r#"<?xml version="1.0" encoding="UTF-16"?>
+<config xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+    <moduleName>Test Mod</moduleName>
+    <requiredInstallFiles>
+        <folder source="core" destination="" />
+    </requiredInstallFiles>
+    <installSteps order="Explicit">
+        <installStep name="Colour">
+            <optionalFileGroups order="Explicit">
+                <group name="Colour" type="SelectExactlyOne">
+                    <plugins order="Explicit">
+                        <plugin name="Red">
+                            <description>Red variant</description>
+                            <files><folder source="options\red" destination="archive\pc\mod" /></files>
+                            <conditionFlags><flag name="colour">red</flag></conditionFlags>
+                            <typeDescriptor><type name="Optional" /></typeDescriptor>
+                        </plugin>
+                        <plugin name="Blue">
+                            <description>Blue variant</description>
+                            <files><file source="options\blue\blue.archive" destination="archive\pc\mod\blue.archive" /></files>
+                            <conditionFlags><flag name="colour">blue</flag></conditionFlags>
+                            <typeDescriptor><type name="Recommended" /></typeDescriptor>
+                        </plugin>
+                    </plugins>
+                </group>
+            </optionalFileGroups>
+        </installStep>
+        <installStep name="Red extras">
+            <visible><flagDependency flag="colour" value="red" /></visible>
+            <optionalFileGroups>
+                <group name="Extras" type="SelectAny">
+                    <plugins>
+                        <plugin name="Glow">
+                            <description>Glow</description>
+                            <typeDescriptor><type name="Optional" /></typeDescriptor>
+                        </plugin>
+                    </plugins>
+                </group>
+            </optionalFileGroups>
+        </installStep>
+    </installSteps>
+    <conditionalFileInstalls>
+        <patterns>
+            <pattern>
+                <dependencies operator="And"><flagDependency flag="colour" value="blue" /></dependencies>
+                <files><file source="patches\blue.reds" destination="r6\scripts\blue.reds" priority="1" /></files>
+            </pattern>
+        </patterns>
+    </conditionalFileInstalls>
+</config>"#;
+
+    fn config() -> FomodConfig {
+        FomodConfig::parse(MODULE_CONFIG, PathBuf::new()).unwrap()
+    }
+
+    #[test]
+    fn should_parse_module_config() {
+        let config = config();
+        assert_eq!(config.module_name, "Test Mod");
+        assert_eq!(config.steps.len(), 2);
+        assert_eq!(config.steps[0].groups[0].group_type, GroupType::SelectExactlyOne);
+        assert_eq!(config.steps[0].groups[0].plugins[1].files[0].destination, normalize_archive_path("archive/pc/mod/blue.archive").unwrap());
+        assert_eq!(config.conditional_installs[0].files[0].priority, 1);
+    }
+
+    #[test]
+    fn should_default_to_recommended_plugin_and_hide_steps_by_flag() {
+        let config = config();
+        let choices = FomodChoices::default();
+        let cyberpunk_dir = Path::new("/nonexistent");
+        assert_eq!(config.selection(0, 0, &choices, &Flags::new(), cyberpunk_dir), vec!["Blue".to_string()]);
+        assert_eq!(config.next_visible_step(1, &choices, cyberpunk_dir), None);
+
+        let files = config.selected_files(&choices, cyberpunk_dir);
+        let sources: Vec<String> = files.iter().map(|file| to_portable_string(&file.source)).collect();
+        assert_eq!(sources, vec!["core", "options/blue/blue.archive", "patches/blue.reds"]);
+    }
+
+    #[test]
+    fn should_follow_group_rules_when_toggling() {
+        let config = config();
+        let mut choices = FomodChoices::default();
+        let cyberpunk_dir = Path::new("/nonexistent");
+        config.toggle(0, 0, 0, &mut choices, cyberpunk_dir);
+        assert_eq!(choices.get("Colour", "Colour"), Some(&vec!["Red".to_string()]));
+        assert_eq!(config.next_visible_step(1, &choices, cyberpunk_dir), Some(1));
+        assert!(config.unmet_groups(0, &choices, cyberpunk_dir).is_empty());
+
+        choices.set("Colour", "Colour", vec![]);
+        assert_eq!(config.unmet_groups(0, &choices, cyberpunk_dir), vec!["Colour"]);
+    }
+
+    #[test]
+    fn should_skip_files_leaving_the_cyberpunk_folder() {
+        let config = FomodConfig::parse(
+            r#"<config>
+    <moduleName>Escape</moduleName>
+    <requiredInstallFiles>
+        <file source="evil.dll" destination="..\..\bin\evil.dll" />
+        <file source="..\outside.reds" destination="r6\scripts\outside.reds" />
+        <file source="C:\Windows\evil.dll" />
+        <folder source="core" destination="" />
+    </requiredInstallFiles>
+</config>"#,
+            PathBuf::new(),
+        )
+        .unwrap();
+        let sources: Vec<String> = config.required_files.iter().map(|file| to_portable_string(&file.source)).collect();
+        assert_eq!(sources, vec!["core"]);
+
+        let temp_mod_path = std::env::temp_dir().join("cyberpunk_mod_manager_tests").join("fomod_escape");
+        if temp_mod_path.exists() {
+            fs::remove_dir_all(&temp_mod_path).unwrap();
+        }
+        fs::create_dir_all(temp_mod_path.join("core").join("r6")).unwrap();
+        fs::write(temp_mod_path.join("core").join("r6").join("a.reds"), "a").unwrap();
+        // packed on Windows, Linux keeps the separators as part of the name
+        fs::write(temp_mod_path.join("core").join("..\\..\\evil.reds"), "evil").unwrap();
+        let mod_files = config.mod_files(&temp_mod_path, &FomodChoices::default(), Path::new("/nonexistent")).unwrap();
+        let destinations: Vec<String> = mod_files.iter().map(|mod_file| to_portable_string(&mod_file.destination)).collect();
+        assert_eq!(destinations, vec!["r6/a.reds"]);
+    }
+
+    #[test]
+    fn should_decode_utf16_configs() {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("<config/>".encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        assert_eq!(decode_xml(&bytes), "<config/>");
+    }
+}
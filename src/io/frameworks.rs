@@ -0,0 +1,210 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use eyre::Result;
+
+use super::{
+    manifest::InstallManifest,
+    mod_type::{Framework, ModClassification, ModType},
+    paths::{normalize_archive_path, CaseResolver},
+};
+
+/// What the dashboard knows about a framework in the Cyberpunk folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameworkInfo {
+    pub framework: Framework,
+    pub installed: bool,
+    pub version: Option<String>,
+    /// The mod of the manifest that installed it, `None` when it was installed by hand
+    pub installed_by: Option<String>,
+}
+
+impl Framework {
+    /// Frameworks that have to be installed for this one to load
+    pub fn requires(&self) -> Vec<Framework> {
+        match self {
+            Framework::ArchiveXl | Framework::TweakXl | Framework::Codeware => vec![Framework::Red4ext],
+            _ => vec![],
+        }
+    }
+
+    pub fn is_installed(&self, cyberpunk_dir: &Path) -> bool {
+        cyberpunk_dir.join(self.resolve(cyberpunk_dir, self.signature())).is_file()
+    }
+
+    fn resolve(&self, cyberpunk_dir: &Path, path: &str) -> PathBuf {
+        CaseResolver::new(cyberpunk_dir).resolve(&normalize_archive_path(path).unwrap_or_default())
+    }
+
+    /// Logs the framework writes on every start, together with a word found on the line holding the version
+    fn version_logs(&self) -> (&str, &str) {
+        match self {
+            Framework::Cet => ("bin/x64/plugins/cyber_engine_tweaks/cyber_engine_tweaks.log", "cet version"),
+            Framework::Red4ext => ("red4ext/logs/red4ext.log", "red4ext"),
+            Framework::Redscript => ("r6/logs/redscript_rCURRENT.log", "redscript"),
+            Framework::ArchiveXl => ("red4ext/plugins/archivexl/archivexl.log", "archivexl"),
+            Framework::TweakXl => ("red4ext/plugins/tweakxl/tweakxl.log", "tweakxl"),
+            Framework::Codeware => ("red4ext/plugins/codeware/codeware.log", "codeware"),
+        }
+    }
+
+    /// The version from the PE version resource of the framework's binary, or from its log
+    pub fn detect_version(&self, cyberpunk_dir: &Path) -> Option<String> {
+        let binary = cyberpunk_dir.join(self.resolve(cyberpunk_dir, self.signature()));
+        if let Some(version) = fs::read(binary).ok().and_then(|bytes| read_pe_version(&bytes)) {
+            return Some(version);
+        }
+        let (log_path, keyword) = self.version_logs();
+        let log = fs::read(cyberpunk_dir.join(self.resolve(cyberpunk_dir, log_path))).ok()?;
+        find_version_in_text(&String::from_utf8_lossy(&log), keyword)
+    }
+}
+
+/// Read the file version of a Windows binary from the `VS_FIXEDFILEINFO` block of its version
+/// resource, found by its signature instead of walking the PE resource tree
+pub fn read_pe_version(bytes: &[u8]) -> Option<String> {
+    const SIGNATURE: [u8; 4] = [0xBD, 0x04, 0xEF, 0xFE];
+    let start = bytes.windows(SIGNATURE.len()).position(|window| window == SIGNATURE)?;
+    let dword = |offset: usize| {
+        bytes
+            .get(start + offset..start + offset + 4)
+            .map(|slice| u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+    };
+    let (most_significant, least_significant) = (dword(8)?, dword(12)?);
+    let mut parts = vec![
+        most_significant >> 16,
+        most_significant & 0xFFFF,
+        least_significant >> 16,
+        least_significant & 0xFFFF,
+    ];
+    if parts.iter().all(|part| *part == 0) {
+        return None;
+    }
+    // 1.2.3.0 reads better as 1.2.3
+    while parts.len() > 3 && parts.last() == Some(&0) {
+        parts.pop();
+    }
+    Some(parts.iter().map(|part| part.to_string()).collect::<Vec<_>>().join("."))
+}
+
+/// The version number (`1.2.3`, optionally prefixed with `v`) on the last line mentioning `keyword`
+pub fn find_version_in_text(text: &str, keyword: &str) -> Option<String> {
+    text.lines()
+        .rev()
+        .filter(|line| line.to_lowercase().contains(keyword))
+        .find_map(|line| {
+            // timestamps come first on a log line, the version comes after them
+            line.split(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+                .rev()
+                .map(|token| token.strip_prefix('v').unwrap_or(token).trim_end_matches('.'))
+                .find(|token| {
+                    let parts: Vec<&str> = token.split('.').collect();
+                    parts.len() >= 2 && parts.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+                })
+                .map(|token| token.to_string())
+        })
+}
+
+/// Every framework with its state in the Cyberpunk folder
+pub fn detect_frameworks(cyberpunk_dir: &Path) -> Result<Vec<FrameworkInfo>> {
+    let owners = InstallManifest::load(cyberpunk_dir)?.owners();
+    Ok(Framework::all()
+        .into_iter()
+        .map(|framework| {
+            let installed = framework.is_installed(cyberpunk_dir);
+            let signature = framework.resolve(cyberpunk_dir, framework.signature());
+            FrameworkInfo {
+                framework,
+                installed,
+                version: if installed { framework.detect_version(cyberpunk_dir) } else { None },
+                installed_by: owners.owner_of(&signature).map(|mod_name| mod_name.to_string()),
+            }
+        })
+        .collect())
+}
+
+/// The frameworks a mod needs, inferred from what it contains
+pub fn required_frameworks(classification: &ModClassification) -> Vec<Framework> {
+    let mut required: Vec<Framework> = classification
+        .types
+        .iter()
+        .flat_map(|mod_type| match mod_type {
+            ModType::ArchiveXl => vec![Framework::ArchiveXl],
+            ModType::Cet => vec![Framework::Cet],
+            ModType::Redscript => vec![Framework::Redscript],
+            ModType::TweakXl => vec![Framework::TweakXl],
+            ModType::Red4ext => vec![Framework::Red4ext],
+            _ => vec![],
+        })
+        .chain(classification.frameworks.iter().flat_map(|framework| framework.requires()))
+        .collect();
+    // the frameworks a mod needs bring their own requirements
+    let indirect: Vec<Framework> = required.iter().flat_map(|framework| framework.requires()).collect();
+    required.extend(indirect);
+    required.retain(|framework| !classification.frameworks.contains(framework));
+    required.sort();
+    required.dedup();
+    required
+}
+
+/// Required frameworks that are not in the Cyberpunk folder
+pub fn missing_frameworks(classification: &ModClassification, cyberpunk_dir: &Path) -> Vec<Framework> {
+    required_frameworks(classification)
+        .into_iter()
+        .filter(|framework| !framework.is_installed(cyberpunk_dir))
+        .collect()
+}
+
+/// An archive in the mod folder that installs `framework`
+pub fn find_framework_archive(mod_types: &HashMap<String, ModClassification>, framework: Framework) -> Option<String> {
+    let mut archives: Vec<&String> = mod_types
+        .iter()
+        .filter(|(_, classification)| classification.frameworks.contains(&framework))
+        .map(|(mod_name, _)| mod_name)
+        .collect();
+    // the highest name is usually the newest version
+    archives.sort();
+    archives.last().map(|mod_name| mod_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_require_frameworks_with_their_dependencies() {
+        let classification = ModClassification {
+            types: vec![ModType::Archive, ModType::ArchiveXl, ModType::Redscript],
+            frameworks: vec![],
+            ..Default::default()
+        };
+        assert_eq!(
+            required_frameworks(&classification),
+            vec![Framework::Red4ext, Framework::Redscript, Framework::ArchiveXl]
+        );
+
+        let tweak_xl = ModClassification {
+            types: vec![ModType::Framework],
+            frameworks: vec![Framework::TweakXl],
+            ..Default::default()
+        };
+        assert_eq!(required_frameworks(&tweak_xl), vec![Framework::Red4ext]);
+    }
+
+    #[test]
+    fn should_read_version_resource() {
+        let mut bytes = vec![0u8; 16];
+        bytes.extend([0xBD, 0x04, 0xEF, 0xFE, 0x00, 0x00, 0x01, 0x00]);
+        // 1.25 and 3.0
+        bytes.extend([25, 0, 1, 0, 0, 0, 3, 0]);
+        assert_eq!(read_pe_version(&bytes), Some("1.25.3".to_string()));
+        assert_eq!(read_pe_version(&[0u8; 32]), None);
+    }
+
+    #[test]
+    fn should_find_version_in_logs() {
+        let log = "[info] Starting\n[info] CET version v1.32.1 [master]\n[info] Done";
+        assert_eq!(find_version_in_text(log, "cet version"), Some("1.32.1".to_string()));
+        let log = "[2024-01-01 10:00:00.000] [RED4ext] [info] RED4ext (v1.25.0) is initializing...";
+        assert_eq!(find_version_in_text(log, "red4ext"), Some("1.25.0".to_string()));
+    }
+}
@@ -1,20 +1,39 @@
-use std::{sync::Arc, env::temp_dir, fs::{File, OpenOptions, self}, io::{Write, Read}, path::Path};
+use std::{sync::Arc, env::temp_dir, fs::{File, self}, io::Read, path::{Path, PathBuf}};
 use crate::{
-    app::{App, utils::{log_help, Settings, check_if_cyberpunk_dir_is_valid}, state::{UiMode, Focus}},
-    constants::{WORKING_DIR_NAME, SAVE_DIR_NAME, SAVE_FILE_NAME, NOT_A_DIRECTORY_ERROR,
+    app::{App, utils::{log_help, Settings, ChoicePopup, FomodWizard, ModPopup, check_if_cyberpunk_dir_is_valid}, state::{UiMode, Focus}},
+    constants::{BACKUP_DIR_NAME, EXTRACTION_CACHE_DIR_NAME, WORKING_DIR_NAME, SAVE_DIR_NAME, SAVE_FILE_NAME, NOT_A_DIRECTORY_ERROR,
         NOT_A_VALID_CYBERPUNK_FOLDER_ERROR, CYBERPUNK_FOLDER_INPUT_EMPTY_ERROR,
         MOD_FOLDER_INPUT_EMPTY_ERROR}
     };
-use compress_tools::{uncompress_archive, Ownership};
-use eyre::Result;
-use fs_extra::dir::CopyOptions;
+use eyre::{eyre, Result};
 use log::{
     error,
-    info, debug,
+    info, debug, warn,
 };
-use walkdir::WalkDir;
 
-use super::IoEvent;
+use super::{
+    IoEvent,
+    backup::BackupStore,
+    batch::{batch_order, BatchOperation, BatchOutcome, BatchSummary},
+    conflicts::{find_conflicts, ConflictResolution},
+    extraction_cache::ExtractionCache,
+    progress::{ProgressHandle, ProgressPhase},
+    fomod::{is_fomod_config, FomodChoiceStore, FomodConfig},
+    frameworks::{detect_frameworks, find_framework_archive, missing_frameworks},
+    installer::{
+        collect_mod_files, detect_archive_root, list_mod_files, listed_destinations, missing_parent_dirs, remove_empty_dirs,
+        resolve_destinations, write_file_atomically, ArchiveRoot, ModFile,
+    },
+    journal::{FileMove, Journal, JournalKind, ManifestChange},
+    load_order::{archive_names, archive_path, current_load_order, merge_load_order, modlist_path, present_archives, sync_modlist, write_modlist},
+    manifest::{hash_file, InstallManifest, InstalledFile, InstalledMod},
+    mod_type::{classify_archives, Framework},
+    paths::{to_portable_string, CaseResolver},
+    plan::{OperationPlan, PlanPreview, PlannedUninstall},
+    profiles::{Profile, ProfileMod, ProfileSwitch},
+    redmod::{find_redmods, forget_empty_redmods, set_redmod_enabled},
+    status::{get_mod_install_status, get_mod_install_statuses, ModInstallStatus},
+};
 
 /// In the IO thread, we handle IO event without blocking the UI thread
 pub struct IoAsyncHandler {
@@ -26,34 +45,52 @@ impl IoAsyncHandler {
         Self { app }
     }
 
-    /// We could be async here
-    pub async fn handle_io_event(&mut self, io_event: IoEvent) {
+    /// Run one dispatched event, the job queue records whether it failed
+    pub async fn handle_io_event(&mut self, io_event: IoEvent) -> Result<()> {
         let result = match io_event {
             IoEvent::Initialize => self.do_initialize().await,
-            IoEvent::InstallMod => {
-                let result = self.install_mod().await;
-                if let Err(err) = result {
-                    error!("Oops, something wrong happened: {:?}", err);
-                } else {
-                    let check_install = self.check_if_mod_is_installed().await;
-                    if let Err(err) = check_install {
-                        error!("Oops, something wrong happened: {:?}", err);
+            IoEvent::InstallMod
+            | IoEvent::InstallModOverwritingConflicts
+            | IoEvent::InstallModSkippingConflicts
+            | IoEvent::SelectArchiveRoot(_)
+            | IoEvent::SelectFomodChoices(_)
+            | IoEvent::InstallModIgnoringFrameworks => {
+                {
+                    let mut app = self.app.lock().await;
+                    if let Some(mod_popup) = app.mod_popup.as_mut() {
+                        match &io_event {
+                            IoEvent::SelectArchiveRoot(archive_root) => mod_popup.archive_root = Some(archive_root.clone()),
+                            IoEvent::SelectFomodChoices(fomod_choices) => mod_popup.fomod_choices = Some(fomod_choices.clone()),
+                            IoEvent::InstallModIgnoringFrameworks => mod_popup.ignore_missing_frameworks = true,
+                            _ => {}
+                        }
                     }
                 }
-                Ok(())
+                let conflict_resolution = match io_event {
+                    IoEvent::InstallModOverwritingConflicts => ConflictResolution::Overwrite,
+                    IoEvent::InstallModSkippingConflicts => ConflictResolution::Skip,
+                    _ => ConflictResolution::Ask,
+                };
+                match self.install_mod_and_dependents(conflict_resolution).await {
+                    Ok(()) => self.check_if_mod_is_installed().await,
+                    err => err,
+                }
             }
-            IoEvent::UninstallMod => {
-                let result = self.uninstall_mod().await;
-                if let Err(err) = result {
-                    error!("Oops, something wrong happened: {:?}", err);
-                } else {
-                    let check_install = self.check_if_mod_is_installed().await;
-                    if let Err(err) = check_install {
-                        error!("Oops, something wrong happened: {:?}", err);
-                    }
+            IoEvent::InstallFramework(framework_archive) => self.install_framework_first(framework_archive).await,
+            IoEvent::UninstallMod => match self.uninstall_mod().await {
+                Ok(()) => self.check_if_mod_is_installed().await,
+                err => err,
+            },
+            IoEvent::DisableMod | IoEvent::EnableMod => {
+                match self.set_mod_enabled(matches!(io_event, IoEvent::EnableMod)).await {
+                    Ok(()) => self.check_if_mod_is_installed().await,
+                    err => err,
                 }
-                Ok(())
             }
+            IoEvent::RepairMod => match self.repair_mod().await {
+                Ok(()) => self.check_if_mod_is_installed().await,
+                err => err,
+            },
             IoEvent::CheckIfModIsInstalled => {
                 let result = self.check_if_mod_is_installed().await;
                 if let Err(_err) = result {
@@ -61,23 +98,76 @@ impl IoAsyncHandler {
                 }
                 Ok(())
             }
+            IoEvent::DetectFrameworks => self.detect_frameworks().await,
+            IoEvent::LoadLoadOrder => self.load_load_order().await,
+            IoEvent::SaveLoadOrder(load_order) => self.save_load_order(load_order).await,
+            IoEvent::LoadRedmods => self.load_redmods().await,
+            IoEvent::ToggleRedmod(folder) => {
+                let result = self.toggle_redmod(folder).await;
+                self.load_redmods().await.and(result)
+            }
+            IoEvent::SaveRedmodOrder(redmod_order) => self.save_redmod_order(redmod_order).await,
+            IoEvent::CompleteJournal | IoEvent::RevertJournal => {
+                self.resolve_journal(matches!(io_event, IoEvent::CompleteJournal)).await
+            }
+            IoEvent::SaveProfile(name) => {
+                let result = self.save_profile(name).await;
+                if result.is_ok() {
+                    self.do_save_settings().await
+                } else {
+                    result
+                }
+            }
+            IoEvent::SwitchProfile(name) => self.switch_profile(name, false).await,
+            IoEvent::ApplyProfile(name) => {
+                let result = self.switch_profile(name, true).await;
+                if let Err(err) = self.refresh_mod_statuses().await {
+                    error!("Oops, something wrong happened: {:?}", err);
+                }
+                // remember the active profile
+                self.do_save_settings().await.and(result)
+            }
+            IoEvent::RunBatch(operation, mod_names) => {
+                let result = self.run_batch(operation, mod_names, !operation.is_planned()).await;
+                self.refresh_mod_statuses().await.and(result)
+            }
+            IoEvent::ApplyBatch(operation, mod_names) => {
+                let result = self.run_batch(operation, mod_names, true).await;
+                self.refresh_mod_statuses().await.and(result)
+            }
             IoEvent::SaveSettings => self.do_save_settings().await,
-            IoEvent::LoadMods => self.do_load_mods(false).await,
+            IoEvent::LoadMods => {
+                let result = self.do_load_mods(false).await;
+                if result.is_ok() {
+                    self.check_for_unfinished_journal().await
+                } else {
+                    result
+                }
+            }
             IoEvent::DeleteTempDir => self.delete_temp_dir().await,
         };
 
-        if let Err(err) = result {
-            error!("Oops, something wrong happen: {:?}", err);
+        if let Err(err) = &result {
+            if self.app.lock().await.progress.is_cancelled() {
+                info!("👍 Cancelled, nothing was changed");
+            } else {
+                error!("Oops, something wrong happen: {:?}", err);
+            }
         }
-
-        let mut app = self.app.lock().await;
-        app.loaded();
+        result
     }
 
     async fn do_initialize(&mut self) -> Result<()> {
         info!("🚀 Initializing the application");
         self.get_saved_settings().await?;
+        let extraction_cache_limit_mb = self.app.lock().await.extraction_cache_limit_mb;
+        if let Err(err) = ExtractionCache::new(extraction_cache_limit_mb * 1024 * 1024).remove_partial_extractions() {
+            warn!("⚠️ Could not clean up the extraction cache: {:?}", err);
+        }
         self.do_load_mods(true).await?;
+        if let Err(err) = self.check_for_unfinished_journal().await {
+            error!("🚫 Could not read the journal of the last operation: {:?}", err);
+        }
         let mut app = self.app.lock().await;
         app.initialized(); // we could update the app state
         if app.state.file_list.state.selected().is_none() {
@@ -88,228 +178,1004 @@ impl IoAsyncHandler {
         Ok(())
     }
 
-    async fn install_mod(&mut self) -> Result<()> {
+    async fn install_mod(&mut self, conflict_resolution: ConflictResolution) -> Result<()> {
         info!("🚀 Installing mod");
-        let app = self.app.lock().await;
+        let mut app = self.app.lock().await;
         let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
-        let mod_file_name = app.mod_popup.as_ref().unwrap().get_mod_name();
+        let mod_popup = app.mod_popup.as_mut().unwrap();
+        let mod_file_name = mod_popup.get_mod_name().to_string();
+        let mod_file_name = mod_file_name.as_str();
+        // a confirmation only counts for the install it was given for
+        let plan_confirmed = std::mem::take(&mut mod_popup.plan_confirmed);
+        let ignore_missing_frameworks = mod_popup.ignore_missing_frameworks;
+        let mut archive_root = mod_popup.archive_root.clone();
+        let fomod_choices = mod_popup.fomod_choices.clone();
         let mod_path = app.mod_folder.clone().unwrap().join(mod_file_name);
-        let temp_dir = temp_dir().join(WORKING_DIR_NAME);
-        // remove extension
-        let temp_mod_path = temp_dir.join(mod_file_name.split('.').next().unwrap());
-        let source = File::open(&mod_path)?;
-        uncompress_archive(source, &temp_mod_path, Ownership::Preserve)?;
-        // check if the temp_mod_path has only .archive files if so copy them to archive/pc/mod in cyberpunk folder else use fs_extra to copy the files recursively to the cyberpunk folder
-        let mut has_only_archive_files = false;
-        for entry in WalkDir::new(&temp_mod_path) {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().unwrap_or_default() == "archive" {
-                has_only_archive_files = true;
-            } else {
-                has_only_archive_files = false;
-                break;
-            }
-        }
-        if has_only_archive_files {
-            // copy the files to the cyberpunk folder
-            for entry in WalkDir::new(&temp_mod_path) {
-                let entry = entry?;
-                let path = entry.path();
-                if path.extension().unwrap_or_default() == "archive" {
-                    let file_name = path.file_name().unwrap().to_str().unwrap();
-                    let dest_path = cyberpunk_dir.join("archive\\pc\\mod").join(file_name);
-                    fs::copy(path, dest_path)?;
+        let classification = app.mod_types.get(mod_file_name).cloned();
+        let extraction_cache = ExtractionCache::new(app.extraction_cache_limit_mb * 1024 * 1024);
+        let progress = app.progress.clone();
+        let new_archive_position = app.new_archive_position;
+        // let the UI draw the progress while the archive is read and extracted
+        drop(app);
+        if Journal::load(&cyberpunk_dir)?.is_some() {
+            error!("🚫 An unfinished operation has to be completed or reverted first");
+            return Ok(());
+        }
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        if manifest.get(mod_file_name).is_some() {
+            error!("🚫 Mod is already installed, uninstall or repair it instead");
+            return Ok(());
+        }
+        let missing = classification
+            .map(|classification| missing_frameworks(&classification, &cyberpunk_dir))
+            .unwrap_or_default();
+        let mut app = self.app.lock().await;
+        app.mod_popup.as_mut().unwrap().missing_frameworks = missing.clone();
+        if !missing.is_empty() && !ignore_missing_frameworks {
+            let missing_names = missing.iter().map(|framework| framework.to_str()).collect::<Vec<_>>().join(", ");
+            warn!("⚠️ {} needs {}, not found in the Cyberpunk folder", mod_file_name, missing_names);
+            let mut choices = vec![("Cancel".to_string(), None)];
+            for framework in &missing {
+                if let Some(framework_archive) = find_framework_archive(&app.mod_types, *framework) {
+                    choices.push((
+                        format!("Install {} first ({})", framework.to_str(), framework_archive),
+                        Some(IoEvent::InstallFramework(framework_archive)),
+                    ));
                 }
             }
-        } else {
-            debug!("🚀 Copying files to the cyberpunk folder");
-            for entry in WalkDir::new(&temp_mod_path) {
-                let entry = entry?;
-                let path = entry.path();
-                let mut copy_options = CopyOptions::new();
-                copy_options.overwrite = true;
-                copy_options.skip_exist = false;
-                // remove mod_path from the start of the path
-                let dir_name = path
-                    .strip_prefix(&temp_mod_path)
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let dest_path = cyberpunk_dir.join(dir_name);
-                debug!("🚀 Copying {} to {}", &path.to_str().unwrap(), &dest_path.to_str().unwrap());
-                // ensure directory exists
-                if let Some(parent) = dest_path.parent() {
-                    fs::create_dir_all(parent)?;
+            choices.push(("Install anyway".to_string(), Some(IoEvent::InstallModIgnoringFrameworks)));
+            app.choice_popup = Some(ChoicePopup::new(
+                "Missing frameworks".to_string(),
+                vec![
+                    format!("{} needs {}, not found in the Cyberpunk folder", mod_file_name, missing_names),
+                    "Without them the mod does nothing or crashes the game".to_string(),
+                ],
+                choices,
+            ));
+            return Ok(());
+        }
+        drop(app);
+        let backup_store = BackupStore::for_mod(&cyberpunk_dir, mod_file_name);
+        let staging_dir = Journal::staging_dir(&cyberpunk_dir, mod_file_name);
+        let owners = manifest.owners();
+        // the listing settles the game root and the conflicts, the archive is only extracted after that.
+        // The files of a FOMOD installer depend on the options, its conflicts are checked once they are picked
+        let listed_files = list_mod_files(&mod_path)?;
+        if !listed_files.iter().any(|path| is_fomod_config(path)) {
+            let root = match archive_root {
+                Some(archive_root) => archive_root,
+                None => match detect_archive_root(&listed_files) {
+                    ArchiveRoot::Detected(archive_root) => archive_root,
+                    ArchiveRoot::Ambiguous(candidates) => {
+                        warn!("⚠️ The archive contains several folders that look like a Cyberpunk folder");
+                        let choices = candidates
+                            .into_iter()
+                            .map(|candidate| {
+                                let label = if candidate.as_os_str().is_empty() {
+                                    "Top of the archive".to_string()
+                                } else {
+                                    to_portable_string(&candidate)
+                                };
+                                (label, Some(IoEvent::SelectArchiveRoot(candidate)))
+                            })
+                            .chain(std::iter::once(("Cancel".to_string(), None)))
+                            .collect();
+                        self.app.lock().await.choice_popup = Some(ChoicePopup::new(
+                            "Choose the folder to install".to_string(),
+                            vec![format!("{} contains several folders that can be installed, pick one", mod_file_name)],
+                            choices,
+                        ));
+                        return Ok(());
+                    }
+                },
+            };
+            let mut resolver = CaseResolver::new(&cyberpunk_dir);
+            let destinations: Vec<PathBuf> = listed_destinations(&listed_files, &root)
+                .iter()
+                .map(|destination| resolver.resolve(destination))
+                .collect();
+            let conflicts = find_conflicts(&owners, mod_file_name, &destinations);
+            if !conflicts.is_empty() && conflict_resolution == ConflictResolution::Ask {
+                warn!("⚠️ {} files are already installed by other mods", conflicts.len());
+                let mut app = self.app.lock().await;
+                app.choice_popup = Some(conflict_popup(mod_file_name, conflicts.len()));
+                app.mod_popup.as_mut().unwrap().conflicts = conflicts;
+                return Ok(());
+            }
+            archive_root = Some(root);
+        }
+        let archive_hash = extraction_cache.archive_hash(&mod_path)?;
+        let temp_mod_path = extraction_cache.extract(&mod_path, &archive_hash, &progress)?;
+        let mut installed_mod = InstalledMod::new(mod_file_name.to_string(), mod_path.clone(), archive_hash);
+        let (mut mod_files, case_collisions) = match FomodConfig::find(&temp_mod_path)? {
+            Some(fomod_config) => {
+                let fomod_choices = match fomod_choices {
+                    Some(fomod_choices) => fomod_choices,
+                    None => {
+                        let remembered_choices = FomodChoiceStore::load(&cyberpunk_dir)?
+                            .mods
+                            .get(mod_file_name)
+                            .cloned()
+                            .unwrap_or_default();
+                        match FomodWizard::new(mod_file_name.to_string(), fomod_config.clone(), remembered_choices.clone(), cyberpunk_dir.clone()) {
+                            Some(fomod_wizard) => {
+                                info!("🚀 {} comes with an installer, pick the options to install", mod_file_name);
+                                self.app.lock().await.fomod_wizard = Some(fomod_wizard);
+                                return Ok(());
+                            }
+                            // nothing to pick, the installer only has required or conditional files
+                            None => remembered_choices,
+                        }
+                    }
+                };
+                info!("🚀 Installing the selected options of {}", fomod_config.display_name());
+                installed_mod.archive_root = fomod_config.root.clone();
+                let mod_files = fomod_config.mod_files(&temp_mod_path, &fomod_choices, &cyberpunk_dir)?;
+                installed_mod.fomod_choices = Some(fomod_choices);
+                resolve_destinations(&cyberpunk_dir, mod_files)
+            }
+            None => {
+                let archive_root = archive_root.unwrap_or_default();
+                if !archive_root.as_os_str().is_empty() {
+                    info!("🚀 Installing from {} inside the archive", to_portable_string(&archive_root));
+                }
+                installed_mod.archive_root = archive_root.clone();
+                resolve_destinations(&cyberpunk_dir, collect_mod_files(&temp_mod_path, &archive_root)?)
+            }
+        };
+        let destinations: Vec<PathBuf> = mod_files.iter().map(|mod_file| mod_file.destination.clone()).collect();
+        let conflicts = find_conflicts(&owners, mod_file_name, &destinations);
+        self.app.lock().await.mod_popup.as_mut().unwrap().conflicts = conflicts.clone();
+        if !conflicts.is_empty() {
+            match conflict_resolution {
+                ConflictResolution::Ask => {
+                    warn!("⚠️ {} files are already installed by other mods", conflicts.len());
+                    self.app.lock().await.choice_popup = Some(conflict_popup(mod_file_name, conflicts.len()));
+                    return Ok(());
+                }
+                ConflictResolution::Skip => {
+                    info!("🚀 Skipping {} conflicting files", conflicts.len());
+                    mod_files.retain(|mod_file| !conflicts.iter().any(|conflict| conflict.path == mod_file.destination));
+                }
+                ConflictResolution::Overwrite => {
+                    info!("🚀 Overwriting {} conflicting files", conflicts.len());
                 }
-                fs_extra::copy_items(&[path], &dest_path.parent().unwrap(), &copy_options)?;
             }
         }
-        // check if duplicate folder is created one up cyberpunk folder if so delete it it will be mod_folder_name
-        let duplicate_folder = cyberpunk_dir.parent().unwrap().join(mod_file_name.split('.').next().unwrap());
-        if duplicate_folder.exists() {
-            debug!("🚀 Deleting duplicate folder {}", duplicate_folder.to_str().unwrap());
-            fs::remove_dir_all(duplicate_folder)?;
+        if !plan_confirmed {
+            let mut plan = OperationPlan::new(format!("Install {}", mod_file_name));
+            for mod_file in &mod_files {
+                plan.write(&cyberpunk_dir, &owners, &mod_file.destination, file_size(&mod_file.source));
+            }
+            for mod_file in &case_collisions {
+                plan.skip(&mod_file.destination, file_size(&mod_file.source));
+            }
+            let confirm = match conflict_resolution {
+                ConflictResolution::Ask => IoEvent::InstallMod,
+                ConflictResolution::Overwrite => IoEvent::InstallModOverwritingConflicts,
+                ConflictResolution::Skip => IoEvent::InstallModSkippingConflicts,
+            };
+            info!("🚀 Check what installing {} changes, press <Enter> to go ahead", mod_file_name);
+            self.app.lock().await.plan_preview = Some(PlanPreview::new(plan, confirm));
+            return Ok(());
         }
+        // stage every file next to the game first so applying the install is only a series of moves
+        debug!("🚀 Staging files in {}", staging_dir.to_string_lossy());
+        let bytes_total = mod_files
+            .iter()
+            .map(|mod_file| fs::metadata(&mod_file.source).map(|metadata| metadata.len()).unwrap_or_default())
+            .sum();
+        progress.phase(ProgressPhase::Copy, mod_files.len() as u64, bytes_total);
+        let mut moves = vec![];
+        for mod_file in mod_files {
+            if progress.is_cancelled() {
+                // nothing was moved into the game yet, dropping the staged copies undoes everything
+                Journal::remove_staging_dir(&cyberpunk_dir, mod_file_name)?;
+                return progress.check_cancelled();
+            }
+            let previous_owner = conflicts
+                .iter()
+                .find(|conflict| conflict.path == mod_file.destination)
+                .map(|conflict| conflict.owner.clone());
+            let staged_path = staging_dir.join(&mod_file.destination);
+            if let Some(parent) = staged_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let size = fs::copy(&mod_file.source, &staged_path)?;
+            progress.file_done(size);
+            let dest_path = cyberpunk_dir.join(&mod_file.destination);
+            for dir in missing_parent_dirs(&cyberpunk_dir, &mod_file.destination) {
+                if !installed_mod.created_dirs.contains(&dir) {
+                    installed_mod.created_dirs.push(dir);
+                }
+            }
+            // keep whatever we are about to replace so uninstall can put it back
+            let backed_up = dest_path.is_file();
+            if backed_up {
+                moves.push(FileMove::new(dest_path.clone(), backup_store.backup_path(&mod_file.destination)));
+            }
+            moves.push(FileMove::new(staged_path, dest_path));
+            installed_mod.files.push(InstalledFile {
+                path: mod_file.destination,
+                size,
+                hash: hash_file(&mod_file.source)?,
+                backed_up,
+                previous_owner,
+                disabled: false,
+            });
+        }
+        let created_dirs = installed_mod.created_dirs.clone();
+        let fomod_choices = installed_mod.fomod_choices.clone();
+        let installed_paths: Vec<PathBuf> = installed_mod.files.iter().map(|installed_file| installed_file.path.clone()).collect();
+        let mut journal = Journal::new(JournalKind::Install, mod_file_name.to_string(), ManifestChange::Insert(installed_mod));
+        journal.moves = moves;
+        journal.created_dirs = created_dirs;
+        debug!("🚀 Copying files to the cyberpunk folder");
+        if let Err(err) = journal.run_cancellable(&cyberpunk_dir, &progress) {
+            if !progress.is_cancelled() {
+                error!("🚫 Install failed, all changes were rolled back");
+            }
+            return Err(err);
+        }
+        if let Some(fomod_choices) = fomod_choices {
+            let mut fomod_choice_store = FomodChoiceStore::load(&cyberpunk_dir)?;
+            fomod_choice_store.mods.insert(mod_file_name.to_string(), fomod_choices);
+            fomod_choice_store.save(&cyberpunk_dir)?;
+        }
+        let new_archives = archive_names(&installed_paths);
+        if let Err(err) = sync_modlist(&cyberpunk_dir, &new_archives, new_archive_position) {
+            warn!("⚠️ Could not update the load order: {:?}", err);
+        } else if !new_archives.is_empty() {
+            info!("👍 New archives are loaded at the {} of the load order", new_archive_position.to_str());
+        }
+        let mut app = self.app.lock().await;
+        app.mod_popup.as_mut().unwrap().conflicts.clear();
         info!("👍 Mod installed");
         Ok(())
     }
 
+    /// Install a framework archive in place of the mod in the popup, the mod is installed once the framework is
+    async fn install_framework_first(&mut self, framework_archive: String) -> Result<()> {
+        let mut app = self.app.lock().await;
+        let mut framework_popup = ModPopup::new(framework_archive.clone());
+        framework_popup.resume_after = app.mod_popup.take().map(Box::new);
+        app.mod_popup = Some(framework_popup);
+        drop(app);
+        info!("🚀 Installing {} first", framework_archive);
+        self.install_mod_and_dependents(ConflictResolution::Ask).await?;
+        self.check_if_mod_is_installed().await
+    }
+
+    /// Install the mod in the popup, then the mods that waited for it as a framework. A mod whose install
+    /// stops for a decision keeps the mods waiting for it until the decision lets it go on
+    async fn install_mod_and_dependents(&mut self, conflict_resolution: ConflictResolution) -> Result<()> {
+        self.install_mod(conflict_resolution).await?;
+        loop {
+            let mut app = self.app.lock().await;
+            if app.choice_popup.is_some() || app.fomod_wizard.is_some() || app.plan_preview.is_some() {
+                return Ok(());
+            }
+            let Some(waiting_popup) = app.mod_popup.as_mut().and_then(|mod_popup| mod_popup.resume_after.take()) else {
+                return Ok(());
+            };
+            info!("🚀 Back to installing {}", waiting_popup.get_mod_name());
+            app.mod_popup = Some(*waiting_popup);
+            drop(app);
+            self.install_mod(ConflictResolution::Ask).await?;
+        }
+    }
+
     async fn uninstall_mod(&mut self) -> Result<()> {
-        let app = self.app.lock().await;
-        // check if the mod is installed
-        if !app.mod_popup.as_ref().unwrap().get_mod_install_status().unwrap_or(false) {
-            error!("🚫 Mod is not installed");
+        let mut app = self.app.lock().await;
+        let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
+        let mod_file_name = app.mod_popup.as_ref().unwrap().get_mod_name().to_string();
+        let mod_file_name = mod_file_name.as_str();
+        let plan_confirmed = std::mem::take(&mut app.mod_popup.as_mut().unwrap().plan_confirmed);
+        let new_archive_position = app.new_archive_position;
+        let progress = app.progress.clone();
+        // checking the files can take a while, let the UI draw the progress meanwhile
+        drop(app);
+        if Journal::load(&cyberpunk_dir)?.is_some() {
+            error!("🚫 An unfinished operation has to be completed or reverted first");
             return Ok(());
         }
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        // the manifest is the only source of truth for what we installed
+        let installed_mod = match manifest.get(mod_file_name) {
+            Some(installed_mod) => installed_mod,
+            None => {
+                error!("🚫 Mod is not installed");
+                return Ok(());
+            }
+        };
+        // the preview worked out the uninstall already, it holds while the mods sharing its files did not change
+        let depends_on: Vec<InstalledMod> = manifest.sharing_files_with(mod_file_name).into_iter().cloned().collect();
+        let planned_uninstall = self.app.lock().await.planned_uninstalls.remove(mod_file_name);
+        let journal = match planned_uninstall.filter(|planned_uninstall| plan_confirmed && planned_uninstall.depends_on == depends_on) {
+            Some(planned_uninstall) => planned_uninstall.journal,
+            None => {
+                let (plan, journal) = plan_uninstall(&cyberpunk_dir, &manifest, installed_mod, &progress)?;
+                if !plan_confirmed {
+                    info!("🚀 Check what uninstalling {} changes, press <Enter> to go ahead", mod_file_name);
+                    let mut app = self.app.lock().await;
+                    app.planned_uninstalls.insert(mod_file_name.to_string(), PlannedUninstall { depends_on, journal });
+                    app.plan_preview = Some(PlanPreview::new(plan, IoEvent::UninstallMod));
+                    return Ok(());
+                }
+                journal
+            }
+        };
         info!("🚀 Uninstalling mod");
+        debug!("🚀 Removing files from the cyberpunk folder");
+        if let Err(err) = journal.run_cancellable(&cyberpunk_dir, &progress) {
+            if !progress.is_cancelled() {
+                error!("🚫 Uninstall failed, all changes were rolled back");
+            }
+            return Err(err);
+        }
+        // drop the archives that are gone from modlist.txt
+        if let Err(err) = sync_modlist(&cyberpunk_dir, &[], new_archive_position) {
+            warn!("⚠️ Could not update the load order: {:?}", err);
+        }
+        let disabled_mod_dir = InstallManifest::disabled_mod_dir(&cyberpunk_dir, mod_file_name);
+        if disabled_mod_dir.exists() {
+            // only the empty folders of the parked files are left
+            fs::remove_dir_all(disabled_mod_dir)?;
+        }
+        let mut manifest = InstallManifest::load(&cyberpunk_dir)?;
+        if !manifest.disabled_redmods.is_empty() {
+            forget_empty_redmods(&cyberpunk_dir, &mut manifest)?;
+            manifest.save(&cyberpunk_dir)?;
+        }
+        info!("👍 Mod uninstalled");
+        Ok(())
+    }
+
+    async fn repair_mod(&mut self) -> Result<()> {
+        let mut app = self.app.lock().await;
         let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
-        let mod_file_name = app.mod_popup.as_ref().unwrap().get_mod_name();
+        let mod_file_name = app.mod_popup.as_ref().unwrap().get_mod_name().to_string();
+        let mod_file_name = mod_file_name.as_str();
+        let plan_confirmed = std::mem::take(&mut app.mod_popup.as_mut().unwrap().plan_confirmed);
         let mod_path = app.mod_folder.clone().unwrap().join(mod_file_name);
-        let temp_dir = temp_dir().join(WORKING_DIR_NAME);
-        // remove extension
-        let temp_mod_path = temp_dir.join(mod_file_name.split('.').next().unwrap());
-        let source = File::open(&mod_path)?;
-        uncompress_archive(source, &temp_mod_path, Ownership::Preserve)?;
-        let mut has_only_archive_files = false;
-        for entry in WalkDir::new(&temp_mod_path) {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().unwrap_or_default() == "archive" {
-                has_only_archive_files = true;
-            } else {
-                has_only_archive_files = false;
-                break;
-            }
-        }
-        if has_only_archive_files {
-            // check if the files exist in the cyberpunk folder
-            for entry in WalkDir::new(&temp_mod_path) {
-                let entry = entry?;
-                let path = entry.path();
-                if path.extension().unwrap_or_default() == "archive" {
-                    let file_name = path.file_name().unwrap().to_str().unwrap();
-                    let dest_path = cyberpunk_dir.join("archive\\pc\\mod").join(file_name);
-                    if dest_path.exists() {
-                        fs::remove_file(dest_path)?;
-                    }
-                }
+        let extraction_cache = ExtractionCache::new(app.extraction_cache_limit_mb * 1024 * 1024);
+        let progress = app.progress.clone();
+        // let the UI draw the progress while the archive is extracted and checked
+        drop(app);
+        if Journal::load(&cyberpunk_dir)?.is_some() {
+            error!("🚫 An unfinished operation has to be completed or reverted first");
+            return Ok(());
+        }
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let mut installed_mod = match manifest.get(mod_file_name) {
+            Some(installed_mod) => installed_mod.clone(),
+            None => {
+                error!("🚫 Mod is not installed, install it instead");
+                return Ok(());
             }
-        } else {
-            debug!("🚀 Removing files from the cyberpunk folder");
-            for entry in WalkDir::new(&temp_mod_path) {
-                let entry = entry?;
-                let path = entry.path();
-                // remove mod_path from the start of the path
-                let dir_name = path
-                    .strip_prefix(&temp_mod_path)
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let dest_path = cyberpunk_dir.join(dir_name);
-                if dest_path.exists() {
-                    if dest_path.is_dir() {
-                        // dont remove any directories
-                    } else {
-                        debug!("🚀 Removing {}", &dest_path.to_str().unwrap());
-                        fs::remove_file(dest_path)?;
-                    }
-                }
+        };
+        if installed_mod.disabled {
+            error!("🚫 Mod is disabled, enable it first");
+            return Ok(());
+        }
+        info!("🚀 Repairing mod");
+        let archive_hash = extraction_cache.archive_hash(&mod_path)?;
+        if archive_hash != installed_mod.archive_hash {
+            warn!("⚠️ The archive changed since the mod was installed, files are repaired from the new archive");
+            installed_mod.archive_hash = archive_hash.clone();
+        }
+        let temp_mod_path = extraction_cache.extract(&mod_path, &archive_hash, &progress)?;
+        let mod_files = match (&installed_mod.fomod_choices, FomodConfig::find(&temp_mod_path)?) {
+            (Some(fomod_choices), Some(fomod_config)) => fomod_config.mod_files(&temp_mod_path, fomod_choices, &cyberpunk_dir)?,
+            _ => collect_mod_files(&temp_mod_path, &installed_mod.archive_root)?,
+        };
+        let (mod_files, _) = resolve_destinations(&cyberpunk_dir, mod_files);
+        let file_count = installed_mod.files.len();
+        let (plan, journal) = plan_repair(&cyberpunk_dir, &manifest, installed_mod, &mod_files, &progress)?;
+        if journal.moves.is_empty() {
+            info!("👍 Nothing to repair, all {} files are intact", file_count);
+            return Ok(());
+        }
+        if !plan_confirmed {
+            // the files are staged again once the plan is confirmed
+            Journal::remove_staging_dir(&cyberpunk_dir, mod_file_name)?;
+            info!("🚀 Check what repairing {} changes, press <Enter> to go ahead", mod_file_name);
+            self.app.lock().await.plan_preview = Some(PlanPreview::new(plan, IoEvent::RepairMod));
+            return Ok(());
+        }
+        if let Err(err) = journal.run_cancellable(&cyberpunk_dir, &progress) {
+            if !progress.is_cancelled() {
+                error!("🚫 Repair failed, all changes were rolled back");
             }
-            // iterate again and remove empty directories
-            for entry in WalkDir::new(&temp_mod_path) {
-                let entry = entry?;
-                let path = entry.path();
-                // remove mod_path from the start of the path
-                let dir_name = path
-                    .strip_prefix(&temp_mod_path)
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let dest_path = cyberpunk_dir.join(dir_name);
-                if dest_path.exists() {
-                    if dest_path.is_dir() {
-                        if dest_path.read_dir()?.next().is_none() {
-                            debug!("🚀 Removing {}", &dest_path.to_str().unwrap());
-                            fs::remove_dir(dest_path)?;
-                        }
+            return Err(err);
+        }
+        let repaired_count = plan.create.len() + plan.overwrite.len();
+        info!(
+            "👍 Mod repaired: {} missing and {} modified files restored, {} files left untouched",
+            plan.create.len(),
+            plan.overwrite.len(),
+            file_count - repaired_count
+        );
+        if !plan.set_aside.is_empty() {
+            let kept_dir = BackupStore::kept_for_mod(&cyberpunk_dir, mod_file_name).backup_path(Path::new(""));
+            warn!("⚠️ The changed files were kept in {}", kept_dir.to_string_lossy());
+        }
+        Ok(())
+    }
+
+    /// Move the files of an installed mod out of the game or back in, without extracting anything
+    async fn set_mod_enabled(&mut self, enable: bool) -> Result<()> {
+        let app = self.app.lock().await;
+        let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
+        let mod_file_name = app.mod_popup.as_ref().unwrap().get_mod_name().to_string();
+        let mod_file_name = mod_file_name.as_str();
+        let new_archive_position = app.new_archive_position;
+        // let the UI draw while the files are moved
+        drop(app);
+        if Journal::load(&cyberpunk_dir)?.is_some() {
+            error!("🚫 An unfinished operation has to be completed or reverted first");
+            return Ok(());
+        }
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let mut installed_mod = match manifest.get(mod_file_name) {
+            Some(installed_mod) => installed_mod.clone(),
+            None => {
+                error!("🚫 Mod is not installed");
+                return Ok(());
+            }
+        };
+        if installed_mod.disabled != enable {
+            info!("👍 Mod is already {}", if enable { "enabled" } else { "disabled" });
+            return Ok(());
+        }
+        let owners = manifest.owners();
+        let backup_store = BackupStore::for_mod(&cyberpunk_dir, mod_file_name);
+        let (kind, verb) = if enable { (JournalKind::Enable, "Enabling") } else { (JournalKind::Disable, "Disabling") };
+        let mut journal = Journal::new(kind, mod_file_name.to_string(), ManifestChange::Insert(installed_mod.clone()));
+        let mut blocked = vec![];
+        for installed_file in installed_mod.files.iter_mut() {
+            let game_path = cyberpunk_dir.join(&installed_file.path);
+            let parked_path = InstallManifest::disabled_location(&cyberpunk_dir, mod_file_name, &installed_file.path);
+            if enable {
+                if !installed_file.disabled {
+                    continue;
+                }
+                // what took the file's place while it was parked has to be what the install replaced
+                let previous_owner = installed_file.previous_owner.as_deref();
+                if let Some(owner) = owners.owner_of(&installed_file.path).filter(|owner| Some(*owner) != previous_owner) {
+                    blocked.push(format!("{} is used by {}", installed_file.path.to_string_lossy(), owner));
+                    continue;
+                }
+                if game_path.exists() {
+                    if !installed_file.backed_up {
+                        blocked.push(format!("{} already exists", installed_file.path.to_string_lossy()));
+                        continue;
                     }
+                    journal.moves.push(FileMove::new(game_path.clone(), backup_store.backup_path(&installed_file.path)));
+                }
+                journal.moves.push(FileMove::new(parked_path, game_path));
+                installed_file.disabled = false;
+            } else {
+                // files other mods overwrote stay where they are
+                if owners.owner_of(&installed_file.path).filter(|owner| *owner != mod_file_name).is_some() {
+                    continue;
                 }
+                let current_path = manifest.location_of(&cyberpunk_dir, mod_file_name, installed_file);
+                journal.moves.push(FileMove::new(current_path, parked_path));
+                if installed_file.backed_up {
+                    journal.moves.push(FileMove::new(backup_store.backup_path(&installed_file.path), game_path));
+                }
+                installed_file.disabled = true;
             }
         }
-        info!("👍 Mod uninstalled");
+        if !blocked.is_empty() {
+            for reason in &blocked {
+                error!("🚫 {}", reason);
+            }
+            error!("🚫 Mod was not enabled, uninstall the mods using its files first");
+            return Ok(());
+        }
+        info!("🚀 {} mod", verb);
+        installed_mod.disabled = !enable;
+        let installed_paths: Vec<PathBuf> = installed_mod.files.iter().map(|installed_file| installed_file.path.clone()).collect();
+        let created_dirs = installed_mod.created_dirs.clone();
+        journal.manifest_change = ManifestChange::Insert(installed_mod);
+        if let Err(err) = journal.run(&cyberpunk_dir) {
+            error!("🚫 {} failed, all changes were rolled back", verb);
+            return Err(err);
+        }
+        if enable {
+            let new_archives = archive_names(&installed_paths);
+            if let Err(err) = sync_modlist(&cyberpunk_dir, &new_archives, new_archive_position) {
+                warn!("⚠️ Could not update the load order: {:?}", err);
+            }
+            info!("👍 Mod enabled");
+        } else {
+            remove_empty_dirs(&cyberpunk_dir, &created_dirs)?;
+            info!("👍 Mod disabled");
+        }
+        Ok(())
+    }
+
+    async fn resolve_journal(&mut self, complete: bool) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let journal = match Journal::load(&cyberpunk_dir)? {
+            Some(journal) => journal,
+            None => return Ok(()),
+        };
+        if complete {
+            info!("🚀 Completing unfinished {} of {}", journal.kind.to_str(), journal.mod_name);
+            journal.complete(&cyberpunk_dir)?;
+        } else {
+            info!("🚀 Reverting unfinished {} of {}", journal.kind.to_str(), journal.mod_name);
+            journal.revert(&cyberpunk_dir)?;
+        }
+        info!("👍 Unfinished {} of {} resolved", journal.kind.to_str(), journal.mod_name);
+        Ok(())
+    }
+
+    /// Ask the user what to do with an operation that was interrupted last time
+    async fn check_for_unfinished_journal(&mut self) -> Result<()> {
+        let Some(cyberpunk_dir) = self.app.lock().await.cyberpunk_folder.clone() else {
+            return Ok(());
+        };
+        if let Some(journal) = Journal::load(&cyberpunk_dir)? {
+            error!("🚫 Found an unfinished {} of {}", journal.kind.to_str(), journal.mod_name);
+            self.app.lock().await.choice_popup = Some(ChoicePopup::new(
+                "Unfinished operation".to_string(),
+                vec![format!(
+                    "The {} of {} was interrupted, do you want to complete or revert it?",
+                    journal.kind.to_str(),
+                    journal.mod_name
+                )],
+                vec![
+                    ("Complete".to_string(), Some(IoEvent::CompleteJournal)),
+                    ("Revert".to_string(), Some(IoEvent::RevertJournal)),
+                    ("Decide later".to_string(), None),
+                ],
+            ));
+        }
         Ok(())
     }
 
     async fn check_if_mod_is_installed(&mut self) -> Result<()> {
         info!("🚀 Checking if mod is installed");
-        let mut install_status = true;
+        let app = self.app.lock().await;
+        let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
+        // the popup may have been closed while a job was running
+        let Some(mod_popup) = app.mod_popup.as_ref() else {
+            return Ok(());
+        };
+        let mod_file_name = mod_popup.get_mod_name().to_string();
+        let mod_names: Vec<String> = app.state.all_files.iter().map(|(name, _)| name.clone()).collect();
+        let classification = app.mod_types.get(&mod_file_name).cloned();
+        // hashing the installed files takes a while, the UI and read-only jobs go on meanwhile
+        drop(app);
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let install_status = get_mod_install_status(&manifest, &cyberpunk_dir, &mod_file_name, true)?;
+        info!("👍 Mod status: {}", install_status.to_str());
+        for detail in install_status.details() {
+            info!("{}", detail);
+        }
+        // installing or uninstalling one mod can change the status of the mods it overlaps with
+        let mut mod_statuses = get_mod_install_statuses(&manifest, &cyberpunk_dir, &mod_names)?;
+        mod_statuses.insert(mod_file_name.clone(), install_status.clone());
+        let missing = classification
+            .map(|classification| missing_frameworks(&classification, &cyberpunk_dir))
+            .unwrap_or_default();
+        if !missing.is_empty() && !install_status.is_installed() {
+            warn!("⚠️ Needs {}", missing.iter().map(|framework| framework.to_str()).collect::<Vec<_>>().join(", "));
+        }
+        let mut app = self.app.lock().await;
+        app.mod_statuses = mod_statuses;
+        if let Some(mod_popup) = app.mod_popup.as_mut().filter(|mod_popup| mod_popup.get_mod_name() == mod_file_name) {
+            mod_popup.set_mod_install_status(install_status);
+            mod_popup.missing_frameworks = missing;
+        }
+        Ok(())
+    }
+    
+    async fn detect_frameworks(&mut self) -> Result<()> {
+        info!("🚀 Looking for installed frameworks");
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let frameworks = detect_frameworks(&cyberpunk_dir)?;
+        let mut app = self.app.lock().await;
+        app.frameworks = frameworks;
+        let installed = app.frameworks.iter().filter(|framework_info| framework_info.installed).count();
+        info!("👍 {} of {} frameworks installed", installed, app.frameworks.len());
+        Ok(())
+    }
+
+    async fn load_load_order(&mut self) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let owners = InstallManifest::load(&cyberpunk_dir)?.owners();
+        let load_order: Vec<(String, Option<String>)> = current_load_order(&cyberpunk_dir)?
+            .into_iter()
+            .map(|archive| {
+                let owner = owners
+                    .owner_of(&archive_path(&cyberpunk_dir, &archive))
+                    .map(|mod_name| mod_name.to_string());
+                (archive, owner)
+            })
+            .collect();
+        let mut app = self.app.lock().await;
+        let selected = app.state.load_order.state.selected();
+        app.state.load_order.items = load_order;
+        app.state.load_order.unselect();
+        if !app.state.load_order.items.is_empty() {
+            let index = selected.unwrap_or_default().min(app.state.load_order.items.len() - 1);
+            app.state.load_order.state.select(Some(index));
+        }
+        info!("👍 {} archives in the load order", app.state.load_order.items.len());
+        Ok(())
+    }
+
+    async fn save_load_order(&mut self, load_order: Vec<String>) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        write_modlist(&cyberpunk_dir, &load_order)?;
+        debug!("👍 Load order saved");
+        Ok(())
+    }
+
+    async fn load_redmods(&mut self) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let mut manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let (redmod_order, disabled_redmods) = (manifest.redmod_order.clone(), manifest.disabled_redmods.clone());
+        let redmods = find_redmods(&cyberpunk_dir, &mut manifest);
+        if manifest.redmod_order != redmod_order || manifest.disabled_redmods != disabled_redmods {
+            manifest.save(&cyberpunk_dir)?;
+        }
+        for redmod in redmods.iter().filter(|redmod| redmod.info.is_none()) {
+            warn!("⚠️ Could not read the info.json of {}", redmod.folder);
+        }
+        let mut app = self.app.lock().await;
+        let selected = app.state.redmods.state.selected();
+        app.state.redmods.items = redmods;
+        app.state.redmods.unselect();
+        if !app.state.redmods.items.is_empty() {
+            let index = selected.unwrap_or_default().min(app.state.redmods.items.len() - 1);
+            app.state.redmods.state.select(Some(index));
+        }
+        let enabled = app.state.redmods.items.iter().filter(|redmod| redmod.enabled).count();
+        info!("👍 {} of {} REDmods enabled", enabled, app.state.redmods.items.len());
+        Ok(())
+    }
+
+    async fn toggle_redmod(&mut self, folder: String) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let mut manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let enable = manifest.disabled_redmods.contains(&folder);
+        set_redmod_enabled(&cyberpunk_dir, &mut manifest, &folder, enable)?;
+        manifest.save(&cyberpunk_dir)?;
+        if enable {
+            info!("👍 {} enabled", folder);
+        } else {
+            info!("👍 {} disabled", folder);
+        }
+        Ok(())
+    }
+
+    async fn save_redmod_order(&mut self, redmod_order: Vec<String>) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let mut manifest = InstallManifest::load(&cyberpunk_dir)?;
+        manifest.redmod_order = redmod_order;
+        manifest.save(&cyberpunk_dir)?;
+        debug!("👍 REDmod order saved");
+        Ok(())
+    }
+
+    /// Record the mods installed right now as a profile, replacing the one with the same name
+    async fn save_profile(&mut self, name: String) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let profile = Profile::capture(name.clone(), &manifest, current_load_order(&cyberpunk_dir)?);
         let mut app = self.app.lock().await;
+        info!("👍 Profile {} saved with {} mods", name, profile.mods.len());
+        let profiles = &mut app.state.profiles;
+        match profiles.items.iter().position(|existing| existing.name == name) {
+            Some(index) => profiles.items[index] = profile,
+            None => profiles.items.push(profile),
+        }
+        let index = profiles.items.iter().position(|existing| existing.name == name);
+        profiles.state.select(index);
+        app.active_profile = Some(name);
+        Ok(())
+    }
+
+    /// Show what switching to a profile changes, or switch once that was confirmed
+    async fn switch_profile(&mut self, name: String, apply: bool) -> Result<()> {
+        let app = self.app.lock().await;
         let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
-        let mod_file_name = app.mod_popup.as_ref().unwrap().get_mod_name();
-        let mod_path = app.mod_folder.clone().unwrap().join(mod_file_name);
-        let temp_dir = temp_dir().join(WORKING_DIR_NAME);
-        // remove extension
-        let temp_mod_path = temp_dir.join(mod_file_name.split('.').next().unwrap());
-        let source = File::open(&mod_path)?;
-        uncompress_archive(source, &temp_mod_path, Ownership::Preserve)?;
-        // compare the files in the temp_mod_path with the files in the cyberpunk folder
-        // if the files are not the same set install_status to false
-        let mut has_only_archive_files = false;
-        for entry in WalkDir::new(&temp_mod_path) {
-            let entry = entry?;
-            let path = entry.path();
-            if path.extension().unwrap_or_default() == "archive" {
-                has_only_archive_files = true;
+        let mod_folder = app.mod_folder.clone().unwrap();
+        let profile = match app.state.profiles.items.iter().find(|profile| profile.name == name) {
+            Some(profile) => profile.clone(),
+            None => {
+                error!("🚫 There is no profile named {}", name);
+                return Ok(());
+            }
+        };
+        drop(app);
+        let switch = ProfileSwitch::plan(&InstallManifest::load(&cyberpunk_dir)?, &profile);
+        if let Some(profile_mod) = switch.install.iter().find(|profile_mod| !mod_folder.join(&profile_mod.mod_name).is_file()) {
+            error!("🚫 {} is not in the mod folder, the profile cannot be applied", profile_mod.mod_name);
+            return Ok(());
+        }
+        if switch.is_empty() {
+            write_profile_load_order(&cyberpunk_dir, &profile)?;
+            self.app.lock().await.active_profile = Some(name.clone());
+            info!("👍 The installed mods already match {}", name);
+            return Ok(());
+        }
+        if !apply {
+            let mod_popup = self.app.lock().await.mod_popup.take();
+            let result = self.plan_profile_switch(&name, &switch).await;
+            let mut app = self.app.lock().await;
+            app.mod_popup = mod_popup;
+            app.plan_preview = Some(PlanPreview::new(result?, IoEvent::ApplyProfile(name.clone())));
+            info!("🚀 Check what switching to {} changes, press <Enter> to go ahead", name);
+            return Ok(());
+        }
+        info!(
+            "🚀 Switching to {}: {} to uninstall, {} to install, {} to enable, {} to disable",
+            name,
+            switch.uninstall.len(),
+            switch.install.len(),
+            switch.enable.len(),
+            switch.disable.len()
+        );
+        let mod_popup = self.app.lock().await.mod_popup.take();
+        let result = self.apply_profile_switch(&switch).await;
+        let mut app = self.app.lock().await;
+        // a prompt of a single mod cannot be answered in the middle of a switch
+        let needs_decision = app.choice_popup.take().is_some() | app.fomod_wizard.take().is_some();
+        let current_mod = app.mod_popup.as_ref().map(|mod_popup| mod_popup.get_mod_name().to_string()).unwrap_or_default();
+        app.mod_popup = mod_popup;
+        if let Err(err) = result {
+            error!("🚫 Switching to {} stopped at {}", name, current_mod);
+            return Err(err);
+        }
+        if needs_decision {
+            error!("🚫 Switching to {} stopped, install {} by hand and switch again", name, current_mod);
+            return Ok(());
+        }
+        drop(app);
+        write_profile_load_order(&cyberpunk_dir, &profile)?;
+        self.app.lock().await.active_profile = Some(name.clone());
+        info!("👍 Switched to {}", name);
+        Ok(())
+    }
+
+    /// The plans of every step of a profile switch, each worked out against the folder as it is now
+    async fn plan_profile_switch(&mut self, name: &str, switch: &ProfileSwitch) -> Result<OperationPlan> {
+        let mut plan = OperationPlan::new(format!("Switch to {}", name));
+        for mod_name in &switch.uninstall {
+            self.app.lock().await.mod_popup = Some(ModPopup::new(mod_name.clone()));
+            self.uninstall_mod().await?;
+            let step_plan = self.app.lock().await.plan_preview.take().map(|preview| preview.plan);
+            plan.extend(format!("Uninstall {}", mod_name), mod_name, step_plan.unwrap_or_default());
+        }
+        for profile_mod in &switch.install {
+            if switch.uninstall.contains(&profile_mod.mod_name) {
+                // its files are only known once the other install is gone
+                plan.steps.push(format!("Install {} again with other options", profile_mod.mod_name));
+                continue;
+            }
+            self.app.lock().await.mod_popup = Some(profile_mod_popup(profile_mod));
+            self.install_mod(ConflictResolution::Overwrite).await?;
+            let mut app = self.app.lock().await;
+            let needs_decision = app.choice_popup.take().is_some() | app.fomod_wizard.take().is_some();
+            let step_plan = app.plan_preview.take().map(|preview| preview.plan).unwrap_or_default();
+            let step = if needs_decision {
+                format!("Install {}, it needs a decision and stops the switch", profile_mod.mod_name)
             } else {
-                has_only_archive_files = false;
-                break;
-            }
-        }
-        if has_only_archive_files {
-            // check if the files exist in the cyberpunk folder
-            for entry in WalkDir::new(&temp_mod_path) {
-                let entry = entry?;
-                let path = entry.path();
-                if path.extension().unwrap_or_default() == "archive" {
-                    let file_name = path.file_name().unwrap().to_str().unwrap();
-                    let dest_path = cyberpunk_dir.join("archive\\pc\\mod").join(file_name);
-                    if !dest_path.exists() {
-                        install_status = false;
-                        break;
-                    }
+                format!("Install {}", profile_mod.mod_name)
+            };
+            // conflicts are overwritten without asking, files of mods the switch keeps are lost to them
+            let taken = step_plan
+                .overwrite
+                .iter()
+                .filter(|planned_file| planned_file.owner.as_ref().is_some_and(|owner| !switch.uninstall.contains(owner)))
+                .cloned();
+            plan.taken.extend(taken);
+            plan.extend(step, &profile_mod.mod_name, step_plan);
+        }
+        plan.steps.extend(switch.enable.iter().map(|mod_name| format!("Enable {}", mod_name)));
+        plan.steps.extend(switch.disable.iter().map(|mod_name| format!("Disable {}", mod_name)));
+        Ok(plan)
+    }
+
+    async fn apply_profile_switch(&mut self, switch: &ProfileSwitch) -> Result<()> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        for mod_name in &switch.uninstall {
+            let mut mod_popup = ModPopup::new(mod_name.clone());
+            // the plan of the whole switch was confirmed
+            mod_popup.plan_confirmed = true;
+            self.app.lock().await.mod_popup = Some(mod_popup);
+            self.uninstall_mod().await?;
+            ensure_done(&cyberpunk_dir, BatchOperation::Uninstall, mod_name)?;
+        }
+        for profile_mod in &switch.install {
+            {
+                let mut mod_popup = profile_mod_popup(profile_mod);
+                mod_popup.plan_confirmed = true;
+                self.app.lock().await.mod_popup = Some(mod_popup);
+            }
+            // mods are installed in their original order so later ones win conflicts again
+            self.install_mod(ConflictResolution::Overwrite).await?;
+            let app = self.app.lock().await;
+            if app.choice_popup.is_some() || app.fomod_wizard.is_some() {
+                return Ok(());
+            }
+            drop(app);
+            ensure_done(&cyberpunk_dir, BatchOperation::Install(ConflictResolution::Overwrite), &profile_mod.mod_name)?;
+        }
+        for (mod_names, operation) in [(&switch.enable, BatchOperation::Enable), (&switch.disable, BatchOperation::Disable)] {
+            for mod_name in mod_names {
+                self.app.lock().await.mod_popup = Some(ModPopup::new(mod_name.clone()));
+                self.set_mod_enabled(operation == BatchOperation::Enable).await?;
+                ensure_done(&cyberpunk_dir, operation, mod_name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Show what running one operation on several mods changes, or run it once that was confirmed,
+    /// one mod at a time. A mod that fails does not stop the others, the summary at the end lists
+    /// what happened to each of them
+    async fn run_batch(&mut self, operation: BatchOperation, mod_names: Vec<String>, apply: bool) -> Result<()> {
+        let mut app = self.app.lock().await;
+        let mod_names = batch_order(&mod_names, &app.mod_types, operation);
+        let mod_popup = app.mod_popup.take();
+        let progress = app.progress.clone();
+        drop(app);
+        if !apply {
+            let result = self.plan_batch(operation, &mod_names).await;
+            let mut app = self.app.lock().await;
+            app.mod_popup = mod_popup;
+            app.plan_preview = Some(PlanPreview::new(result?, IoEvent::ApplyBatch(operation, mod_names.clone())));
+            info!(
+                "🚀 Check what the {} of {} mods changes, press <Enter> to go ahead",
+                operation.to_str().to_lowercase(),
+                mod_names.len()
+            );
+            return Ok(());
+        }
+        info!("🚀 {} of {} mods: {}", operation.to_str(), mod_names.len(), mod_names.join(", "));
+        let mut summary = BatchSummary::default();
+        for mod_name in &mod_names {
+            if progress.is_cancelled() {
+                summary.add(mod_name.clone(), BatchOutcome::Skipped("Cancelled".to_string()));
+                continue;
+            }
+            let outcome = match self.run_batch_step(operation, mod_name).await {
+                Ok(outcome) => outcome,
+                Err(_) if progress.is_cancelled() => BatchOutcome::Skipped("Cancelled, rolled back".to_string()),
+                Err(err) => {
+                    error!("🚫 {} of {} failed: {:?}", operation.to_str(), mod_name, err);
+                    BatchOutcome::Failed(err.to_string())
                 }
+            };
+            summary.add(mod_name.clone(), outcome);
+        }
+        let mut app = self.app.lock().await;
+        app.mod_popup = mod_popup;
+        let lines = summary.lines(operation);
+        for line in &lines {
+            info!("{}", line);
+        }
+        app.state.marked = summary.not_done();
+        app.choice_popup = Some(ChoicePopup::new(
+            format!("{} finished", operation.to_str()),
+            lines,
+            vec![("Close".to_string(), None)],
+        ));
+        info!("👍 {} of {} mods finished", operation.to_str(), mod_names.len());
+        Ok(())
+    }
+
+    /// The plans of every mod of a batch, each worked out against the folder as it is now
+    async fn plan_batch(&mut self, operation: BatchOperation, mod_names: &[String]) -> Result<OperationPlan> {
+        let app = self.app.lock().await;
+        let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
+        let mod_types = app.mod_types.clone();
+        drop(app);
+        // frameworks of the batch are installed before the mods needing them, those mods do not ask for them
+        let batch_frameworks: Vec<Framework> = mod_names
+            .iter()
+            .filter_map(|mod_name| mod_types.get(mod_name))
+            .flat_map(|classification| classification.frameworks.clone())
+            .collect();
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let mut plan = OperationPlan::new(format!("{} {} mods", operation.to_str(), mod_names.len()));
+        for mod_name in mod_names {
+            if let Some(skip_reason) = operation.skip_reason(manifest.get(mod_name)) {
+                plan.steps.push(format!("Skip {}, {}", mod_name, skip_reason));
+                continue;
             }
-        } else {
-            for entry in WalkDir::new(&temp_mod_path) {
-                let entry = entry?;
-                let path = entry.path();
-                // remove mod_path from the start of the path
-                let dir_name = path
-                    .strip_prefix(&temp_mod_path)
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let dest_path = cyberpunk_dir.join(dir_name);
-                if !dest_path.exists() {
-                    install_status = false;
-                    break;
+            let mut mod_popup = ModPopup::new(mod_name.clone());
+            mod_popup.ignore_missing_frameworks = mod_types.get(mod_name).is_some_and(|classification| {
+                missing_frameworks(classification, &cyberpunk_dir)
+                    .iter()
+                    .all(|framework| batch_frameworks.contains(framework))
+            });
+            self.app.lock().await.mod_popup = Some(mod_popup);
+            let result = match operation {
+                BatchOperation::Install(conflict_resolution) => self.install_mod(conflict_resolution).await,
+                _ => self.uninstall_mod().await,
+            };
+            let mut app = self.app.lock().await;
+            let mut needs_decision = app.choice_popup.take().is_some() | app.fomod_wizard.take().is_some();
+            let step_plan = app.plan_preview.take().map(|preview| preview.plan).unwrap_or_default();
+            drop(app);
+            // the mod is planned before the earlier steps ran, their files only conflict once they did
+            needs_decision |= operation == BatchOperation::Install(ConflictResolution::Ask) && plan.writes_over_steps(&step_plan);
+            match result {
+                Err(err) => plan.steps.push(format!("{} {}, it fails: {}", operation.to_str(), mod_name, err)),
+                Ok(()) if needs_decision => {
+                    plan.steps.push(format!("{} {}, it needs a decision and is skipped", operation.to_str(), mod_name))
                 }
+                Ok(()) => plan.extend(format!("{} {}", operation.to_str(), mod_name), mod_name, step_plan),
             }
         }
-        app.mod_popup.as_mut().unwrap().set_mod_install_status(install_status);
-        if install_status {
-            info!("👍 Mod is installed");
-        } else {
-            info!("👍 Mod is not installed");
+        Ok(plan)
+    }
+
+    async fn run_batch_step(&mut self, operation: BatchOperation, mod_name: &str) -> Result<BatchOutcome> {
+        let cyberpunk_dir = self.app.lock().await.cyberpunk_folder.clone().unwrap();
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        if let Some(skip_reason) = operation.skip_reason(manifest.get(mod_name)) {
+            return Ok(BatchOutcome::Skipped(skip_reason.to_string()));
+        }
+        if operation == BatchOperation::Verify {
+            let install_status = get_mod_install_status(&manifest, &cyberpunk_dir, mod_name, true)?;
+            return Ok(match install_status {
+                ModInstallStatus::PartiallyInstalled { .. } | ModInstallStatus::Modified { .. } => {
+                    BatchOutcome::Failed(format!("{}, repair it from its popup", install_status.to_str()))
+                }
+                _ => BatchOutcome::Done,
+            });
+        }
+        let mut mod_popup = ModPopup::new(mod_name.to_string());
+        // the batch was confirmed as a whole
+        mod_popup.plan_confirmed = true;
+        self.app.lock().await.mod_popup = Some(mod_popup);
+        match operation {
+            BatchOperation::Install(conflict_resolution) => self.install_mod(conflict_resolution).await?,
+            BatchOperation::Uninstall => self.uninstall_mod().await?,
+            BatchOperation::Enable | BatchOperation::Disable => {
+                self.set_mod_enabled(operation == BatchOperation::Enable).await?
+            }
+            BatchOperation::Verify => {}
+        }
+        // a prompt of a single mod cannot be answered in the middle of a batch
+        {
+            let mut app = self.app.lock().await;
+            if let Some(choice_popup) = app.choice_popup.take() {
+                return Ok(BatchOutcome::Skipped(format!("{}, open its popup to decide", choice_popup.title)));
+            }
+            if app.fomod_wizard.take().is_some() {
+                return Ok(BatchOutcome::Skipped("has a FOMOD installer, open its popup to pick options".to_string()));
+            }
         }
+        Ok(if operation.is_done(&InstallManifest::load(&cyberpunk_dir)?, mod_name) {
+            BatchOutcome::Done
+        } else {
+            BatchOutcome::Failed("nothing was changed, see the log".to_string())
+        })
+    }
+
+    /// Statuses of the file list after several mods changed at once
+    async fn refresh_mod_statuses(&mut self) -> Result<()> {
+        let app = self.app.lock().await;
+        let cyberpunk_dir = app.cyberpunk_folder.clone().unwrap();
+        let mod_names: Vec<String> = app.state.all_files.iter().map(|(name, _)| name.clone()).collect();
+        drop(app);
+        let manifest = InstallManifest::load(&cyberpunk_dir)?;
+        let mod_statuses = get_mod_install_statuses(&manifest, &cyberpunk_dir, &mod_names)?;
+        self.app.lock().await.mod_statuses = mod_statuses;
         Ok(())
     }
-    
+
     async fn do_save_settings(&mut self) -> Result<()> {
         info!("🚀 Saving settings");
         let app = self.app.lock().await;
@@ -329,17 +1195,18 @@ impl IoAsyncHandler {
             cyberpunk_folder = Some(cyberpunk_folder.unwrap());
         }
         let settings = Settings {
-            mod_folder: mod_folder,
-            cyberpunk_folder: cyberpunk_folder,
+            mod_folder,
+            cyberpunk_folder,
+            new_archive_position: app.new_archive_position,
+            profiles: app.state.profiles.items.clone(),
+            active_profile: app.active_profile.clone(),
+            extraction_cache_limit_mb: app.extraction_cache_limit_mb,
         };
+        drop(app);
         let settings_json = serde_json::to_string(&settings)?;
         let save_file_path = temp_dir().join(SAVE_DIR_NAME).join(SAVE_FILE_NAME);
-        // if file is not present create it, else overwrite it
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(save_file_path)?;
-        file.write_all(settings_json.as_bytes())?;
+        // the profiles live in this file, a crash while writing must not lose them
+        write_file_atomically(&save_file_path, settings_json)?;
         info!("👍 Settings saved");
         Ok(())
     }
@@ -358,16 +1225,12 @@ impl IoAsyncHandler {
         let settings: Settings = serde_json::from_str(&settings_json)?;
         let mut app = self.app.lock().await;
         // if the saved settings are empty set None
-        if settings.mod_folder.is_none() || settings.mod_folder.as_ref().unwrap().to_str().unwrap() == "" {
-            app.mod_folder = None;
-        } else {
-            app.mod_folder = Some(settings.mod_folder.unwrap());
-        }
-        if settings.cyberpunk_folder.is_none() || settings.cyberpunk_folder.as_ref().unwrap().to_str().unwrap() == "" {
-            app.cyberpunk_folder = None;
-        } else {
-            app.cyberpunk_folder = Some(settings.cyberpunk_folder.unwrap());
-        }
+        app.mod_folder = settings.mod_folder.filter(|folder| !folder.as_os_str().is_empty());
+        app.cyberpunk_folder = settings.cyberpunk_folder.filter(|folder| !folder.as_os_str().is_empty());
+        app.new_archive_position = settings.new_archive_position;
+        app.state.profiles.items = settings.profiles;
+        app.active_profile = settings.active_profile;
+        app.extraction_cache_limit_mb = settings.extraction_cache_limit_mb;
         info!("👍 Saved settings loaded");
         Ok(())
     }
@@ -406,16 +1269,22 @@ impl IoAsyncHandler {
         if mod_folder_path.is_dir() {
             app.mod_folder = Some(mod_folder_path.to_path_buf());
             let mut files = vec![];
-            for entry in fs::read_dir(mod_folder_path).unwrap() {
-                if let Ok(entry) = entry {
-                    if let Ok(metadata) = entry.metadata() {
-                        if metadata.is_file() {
-                            files.push((entry.file_name().to_string_lossy().to_string(), metadata.len() as usize));
-                        }
+            for entry in fs::read_dir(mod_folder_path).unwrap().flatten() {
+                if let Ok(metadata) = entry.metadata() {
+                    if metadata.is_file() {
+                        files.push((entry.file_name().to_string_lossy().to_string(), metadata.len() as usize));
                     }
                 }
             }
-            app.state.file_list.items = files;
+            // reading every archive index takes a while, let the UI draw meanwhile
+            drop(app);
+            let file_names: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+            let mod_types = classify_archives(mod_folder_path, &file_names);
+            app = self.app.lock().await;
+            app.mod_types = mod_types;
+            app.state.marked.retain(|name| file_names.contains(name));
+            app.state.all_files = files;
+            app.apply_type_filter();
             mod_folder_ok = true;
         } else {
             // check if input is empty, put error message in temp input store
@@ -426,12 +1295,27 @@ impl IoAsyncHandler {
             }
         }
         if cyberpunk_folder_path.is_dir() {
-            if !check_if_cyberpunk_dir_is_valid(cyberpunk_folder_path.clone().to_path_buf()) {
-                app.state.select_folder_form[1] = format!("{} {}", cyberpunk_folder_input, NOT_A_VALID_CYBERPUNK_FOLDER_ERROR);
-                return Ok(());
+            // walking the Cyberpunk folder takes a while, let the UI draw meanwhile
+            let mod_names: Vec<String> = app.state.all_files.iter().map(|(name, _)| name.clone()).collect();
+            drop(app);
+            let mod_statuses = if check_if_cyberpunk_dir_is_valid(cyberpunk_folder_path.to_path_buf()) {
+                // a quick status for every mod in the list, the popup verifies hashes
+                let manifest = InstallManifest::load(cyberpunk_folder_path)?;
+                Some(get_mod_install_statuses(&manifest, cyberpunk_folder_path, &mod_names)?)
             } else {
-                app.cyberpunk_folder = Some(cyberpunk_folder_path.to_path_buf());
-                cyberpunk_folder_ok = true;
+                None
+            };
+            app = self.app.lock().await;
+            match mod_statuses {
+                Some(mod_statuses) => {
+                    app.cyberpunk_folder = Some(cyberpunk_folder_path.to_path_buf());
+                    app.mod_statuses = mod_statuses;
+                    cyberpunk_folder_ok = true;
+                }
+                None => {
+                    app.state.select_folder_form[1] = format!("{} {}", cyberpunk_folder_input, NOT_A_VALID_CYBERPUNK_FOLDER_ERROR);
+                    return Ok(());
+                }
             }
         } else {
             // check if input is empty, put error message in temp input store
@@ -461,21 +1345,536 @@ impl IoAsyncHandler {
         let mut temp_dir = temp_dir();
         temp_dir.push(WORKING_DIR_NAME);
         // check if it exists, if so delete everything including folders in it except for SAVE_FILE_NAME
+        // and the extraction cache, which is kept between runs
         if temp_dir.exists() {
-            for entry in fs::read_dir(temp_dir.clone()).unwrap() {
-                if let Ok(entry) = entry {
-                    if let Ok(metadata) = entry.metadata() {
-                        if metadata.is_file() {
-                            if entry.file_name().to_string_lossy().to_string() != SAVE_FILE_NAME {
-                                fs::remove_file(entry.path()).unwrap();
-                            }
-                        } else {
-                            fs::remove_dir_all(entry.path()).unwrap();
+            for entry in fs::read_dir(temp_dir.clone()).unwrap().flatten() {
+                if let Ok(metadata) = entry.metadata() {
+                    if metadata.is_file() {
+                        if entry.file_name().to_string_lossy() != SAVE_FILE_NAME {
+                            fs::remove_file(entry.path()).unwrap();
                         }
+                    } else if entry.file_name().to_string_lossy() != EXTRACTION_CACHE_DIR_NAME {
+                        fs::remove_dir_all(entry.path()).unwrap();
                     }
                 }
             }
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+/// What uninstalling a mod changes and the journal doing it, worked out without touching the game.
+/// Files of the mod that other mods overwrote are handed over to them with what they replaced
+fn plan_uninstall(
+    cyberpunk_dir: &Path,
+    manifest: &InstallManifest,
+    installed_mod: &InstalledMod,
+    progress: &ProgressHandle,
+) -> Result<(OperationPlan, Journal)> {
+    let mod_file_name = installed_mod.mod_name.as_str();
+    let owners = manifest.owners();
+    let backup_store = BackupStore::for_mod(cyberpunk_dir, mod_file_name);
+    let staging_dir = Journal::staging_dir(cyberpunk_dir, mod_file_name);
+    let mut journal = Journal::new(JournalKind::Uninstall, mod_file_name.to_string(), ManifestChange::Remove(mod_file_name.to_string()));
+    journal.created_dirs = installed_mod.created_dirs.clone();
+    let mut plan = OperationPlan::new(format!("Uninstall {}", mod_file_name));
+    // mods that overwrote files of this one, they inherit what this mod replaced
+    let mut successors: Vec<InstalledMod> = vec![];
+    let bytes_total = installed_mod.files.iter().map(|installed_file| installed_file.size).sum();
+    progress.phase(ProgressPhase::Verify, installed_mod.files.len() as u64, bytes_total);
+    for installed_file in &installed_mod.files {
+        // nothing is touched before the journal runs, stopping here leaves the mod as it was
+        progress.check_cancelled()?;
+        progress.file_done(installed_file.size);
+        let path = &installed_file.path;
+        let dest_path = manifest.location_of(cyberpunk_dir, mod_file_name, installed_file);
+        let successor = owners.successor_of(mod_file_name, path).and_then(|successor| manifest.get(successor));
+        if let Some(successor) = successor {
+            let successor = match successors.iter().position(|updated| updated.mod_name == successor.mod_name) {
+                Some(index) => &mut successors[index],
+                None => {
+                    successors.push(successor.clone());
+                    successors.last_mut().unwrap()
+                }
+            };
+            let successor_backups = BackupStore::for_mod(cyberpunk_dir, &successor.mod_name);
+            let successor_file = successor.files.iter_mut().find(|successor_file| successor_file.path == *path).unwrap();
+            if !successor_file.disabled {
+                // the file in the game is the successor's, only what it replaced changes hands
+                if successor_file.backed_up {
+                    journal.moves.push(FileMove::new(successor_backups.backup_path(path), staging_dir.join(BACKUP_DIR_NAME).join(path)));
+                }
+                if installed_file.backed_up {
+                    journal.moves.push(FileMove::new(backup_store.backup_path(path), successor_backups.backup_path(path)));
+                }
+                successor_file.backed_up = installed_file.backed_up;
+                successor_file.previous_owner = installed_file.previous_owner.clone();
+                info!("🔁 {} now belongs to {} alone", path.to_string_lossy(), successor.mod_name);
+                plan.reassign(path, successor_file.size, &successor.mod_name);
+                continue;
+            }
+            // the successor is disabled, the game has this mod's file which is removed below
+            // and the successor replaces whatever is left when it is enabled again
+            successor_file.backed_up = installed_file.backed_up;
+            successor_file.previous_owner = installed_file.previous_owner.clone();
+        } else if let Some(owner) = owners.owner_of(path).filter(|owner| *owner != mod_file_name && !installed_file.disabled) {
+            warn!("⚠️ {} is used by {}, left in place", path.to_string_lossy(), owner);
+            continue;
+        }
+        if !dest_path.is_file() {
+            warn!("⚠️ {} is missing", path.to_string_lossy());
+            if installed_file.backed_up && !installed_file.disabled {
+                plan.restore(path, file_size(&backup_store.backup_path(path)));
+                journal.moves.push(FileMove::new(backup_store.backup_path(path), cyberpunk_dir.join(path)));
+            }
+            continue;
+        }
+        // parked files of a disabled mod are only ever touched by the manager
+        if !installed_file.disabled && !installed_file.is_unchanged(&dest_path)? {
+            warn!("⚠️ {} was changed after it was installed, left in place", path.to_string_lossy());
+            plan.keep(path, file_size(&dest_path));
+            if installed_file.backed_up {
+                let kept_path = BackupStore::kept_for_mod(cyberpunk_dir, mod_file_name).backup_path(path);
+                warn!("⚠️ The original it replaced was kept in {}", kept_path.to_string_lossy());
+                plan.set_aside(path, file_size(&backup_store.backup_path(path)));
+                journal.moves.push(FileMove::new(backup_store.backup_path(path), kept_path));
+            }
+            continue;
+        }
+        // removed files are parked in staging until the whole uninstall went through
+        journal.moves.push(FileMove::new(dest_path, staging_dir.join(path)));
+        // disabling the mod already put the original back
+        if !installed_file.disabled {
+            plan.remove(&owners, path, installed_file.size);
+            if installed_file.backed_up {
+                plan.restore(path, file_size(&backup_store.backup_path(path)));
+                journal.moves.push(FileMove::new(backup_store.backup_path(path), cyberpunk_dir.join(path)));
+            }
+        }
+    }
+    if !successors.is_empty() {
+        journal.manifest_change = ManifestChange::Reassign(mod_file_name.to_string(), successors);
+    }
+    Ok((plan, journal))
+}
+
+/// What repairing an installed mod changes and the journal doing it, with the archive files that
+/// are missing or differ from the archive staged. Files other mods overwrote since and files that
+/// are still intact are left alone, changed files are moved into the kept backups of the mod
+fn plan_repair(
+    cyberpunk_dir: &Path,
+    manifest: &InstallManifest,
+    mut installed_mod: InstalledMod,
+    mod_files: &[ModFile],
+    progress: &ProgressHandle,
+) -> Result<(OperationPlan, Journal)> {
+    let mod_file_name = installed_mod.mod_name.clone();
+    let mod_file_name = mod_file_name.as_str();
+    let staging_dir = Journal::staging_dir(cyberpunk_dir, mod_file_name);
+    let kept_backups = BackupStore::kept_for_mod(cyberpunk_dir, mod_file_name);
+    let owners = manifest.owners();
+    let mut journal = Journal::new(JournalKind::Repair, mod_file_name.to_string(), ManifestChange::Insert(installed_mod.clone()));
+    let mut plan = OperationPlan::new(format!("Repair {}", mod_file_name));
+    let bytes_total = installed_mod.files.iter().map(|installed_file| installed_file.size).sum();
+    progress.phase(ProgressPhase::Verify, installed_mod.files.len() as u64, bytes_total);
+    for installed_file in installed_mod.files.iter_mut() {
+        if progress.is_cancelled() {
+            Journal::remove_staging_dir(cyberpunk_dir, mod_file_name)?;
+            progress.check_cancelled()?;
+        }
+        progress.file_done(installed_file.size);
+        // files another mod overwrote since are theirs now
+        if owners.owner_of(&installed_file.path).filter(|owner| *owner != mod_file_name).is_some() {
+            continue;
+        }
+        let mod_file = match mod_files.iter().find(|mod_file| mod_file.destination == installed_file.path) {
+            Some(mod_file) => mod_file,
+            None => {
+                warn!("⚠️ {} is no longer part of the archive, leaving it alone", installed_file.path.to_string_lossy());
+                continue;
+            }
+        };
+        let dest_path = manifest.location_of(cyberpunk_dir, mod_file_name, installed_file);
+        let archive_file_hash = hash_file(&mod_file.source)?;
+        if dest_path.is_file() {
+            if hash_file(&dest_path)? == archive_file_hash {
+                continue;
+            }
+            // whatever was changed in the file is kept, it may have been on purpose
+            plan.set_aside(&installed_file.path, file_size(&dest_path));
+            journal.moves.push(FileMove::new(dest_path.clone(), kept_backups.backup_path(&installed_file.path)));
+        } else {
+            journal.created_dirs.extend(missing_parent_dirs(cyberpunk_dir, &installed_file.path));
+        }
+        let staged_path = staging_dir.join(&installed_file.path);
+        if let Some(parent) = staged_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        installed_file.size = fs::copy(&mod_file.source, &staged_path)?;
+        installed_file.hash = archive_file_hash;
+        plan.write(cyberpunk_dir, &owners, &installed_file.path, installed_file.size);
+        journal.moves.push(FileMove::new(staged_path, dest_path));
+    }
+    journal.manifest_change = ManifestChange::Insert(installed_mod);
+    Ok((plan, journal))
+}
+
+/// Ask what to do with the files of a mod that other mods already installed
+fn conflict_popup(mod_file_name: &str, conflict_count: usize) -> ChoicePopup {
+    ChoicePopup::new(
+        "Conflicting files".to_string(),
+        vec![format!(
+            "{} files of {} are already installed by other mods, see the mod popup for the full list",
+            conflict_count, mod_file_name
+        )],
+        vec![
+            ("Cancel".to_string(), None),
+            ("Overwrite conflicting files".to_string(), Some(IoEvent::InstallModOverwritingConflicts)),
+            ("Skip conflicting files".to_string(), Some(IoEvent::InstallModSkippingConflicts)),
+        ],
+    )
+}
+
+/// Fails when the manifest shows an operation of a larger one did nothing, the rest must not run on top of it
+fn ensure_done(cyberpunk_dir: &Path, operation: BatchOperation, mod_name: &str) -> Result<()> {
+    if !operation.is_done(&InstallManifest::load(cyberpunk_dir)?, mod_name) {
+        return Err(eyre!("{} of {} changed nothing, see the log", operation.to_str(), mod_name));
+    }
+    Ok(())
+}
+
+/// Put the archives of a profile in its load order, archives it does not know about go last
+fn write_profile_load_order(cyberpunk_dir: &Path, profile: &Profile) -> Result<()> {
+    if !profile.load_order.is_empty() || modlist_path(cyberpunk_dir).exists() {
+        write_modlist(cyberpunk_dir, &merge_load_order(&profile.load_order, &present_archives(cyberpunk_dir)?))?;
+    }
+    Ok(())
+}
+
+/// The popup a profile installs a mod from, with the options it was installed with before
+fn profile_mod_popup(profile_mod: &ProfileMod) -> ModPopup {
+    let mut mod_popup = ModPopup::new(profile_mod.mod_name.clone());
+    mod_popup.archive_root = Some(profile_mod.archive_root.clone());
+    mod_popup.fomod_choices = profile_mod.fomod_choices.clone();
+    // the profile worked like this before, missing frameworks are not asked about again
+    mod_popup.ignore_missing_frameworks = true;
+    mod_popup
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|metadata| metadata.len()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::plan::PlannedFile;
+
+    fn game_dir(name: &str) -> PathBuf {
+        let dir = temp_dir().join("cyberpunk_mod_manager_tests").join(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(dir.join("archive").join("pc").join("mod")).unwrap();
+        dir
+    }
+
+    /// Write `files` into the game folder as installed by `mod_name`, returning the recorded mod
+    fn install(cyberpunk_dir: &Path, mod_name: &str, files: &[(&str, &str, Option<&str>)]) -> InstalledMod {
+        let mut installed_mod = InstalledMod::new(mod_name.to_string(), PathBuf::from(mod_name), String::new());
+        for (path, contents, previous_owner) in files {
+            let dest_path = cyberpunk_dir.join(path);
+            fs::create_dir_all(dest_path.parent().unwrap()).unwrap();
+            fs::write(&dest_path, contents).unwrap();
+            installed_mod.files.push(InstalledFile {
+                path: PathBuf::from(path),
+                size: contents.len() as u64,
+                hash: hash_file(&dest_path).unwrap(),
+                backed_up: false,
+                previous_owner: previous_owner.map(|owner| owner.to_string()),
+                disabled: false,
+            });
+        }
+        installed_mod
+    }
+
+    fn handler(cyberpunk_dir: &Path, mod_name: &str) -> IoAsyncHandler {
+        let (io_tx, _) = tokio::sync::mpsc::channel(1);
+        let mut app = App::new(io_tx);
+        app.cyberpunk_folder = Some(cyberpunk_dir.to_path_buf());
+        app.mod_popup = Some(ModPopup::new(mod_name.to_string()));
+        IoAsyncHandler::new(Arc::new(tokio::sync::Mutex::new(app)))
+    }
+
+    async fn set_mod_enabled(cyberpunk_dir: &Path, mod_name: &str, enable: bool) {
+        handler(cyberpunk_dir, mod_name).set_mod_enabled(enable).await.unwrap();
+    }
+
+    fn status(cyberpunk_dir: &Path, mod_name: &str) -> ModInstallStatus {
+        get_mod_install_status(&InstallManifest::load(cyberpunk_dir).unwrap(), cyberpunk_dir, mod_name, true).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_disable_and_enable_mods() {
+        let cyberpunk_dir = game_dir("disable_enable");
+        let mut manifest = InstallManifest::default();
+        manifest.insert(install(&cyberpunk_dir, "mod.zip", &[("archive/pc/mod/a.archive", "a", None), ("r6/scripts/a.reds", "a", None)]));
+        manifest.save(&cyberpunk_dir).unwrap();
+
+        set_mod_enabled(&cyberpunk_dir, "mod.zip", false).await;
+        assert_eq!(status(&cyberpunk_dir, "mod.zip"), ModInstallStatus::Disabled);
+        assert!(!cyberpunk_dir.join("archive/pc/mod/a.archive").exists());
+        assert!(!cyberpunk_dir.join("r6/scripts/a.reds").exists());
+        // archives are renamed in place, everything else is parked in the data folder
+        let parked_archive = InstallManifest::disabled_location(&cyberpunk_dir, "mod.zip", Path::new("archive/pc/mod/a.archive"));
+        assert_eq!(parked_archive.parent(), Some(cyberpunk_dir.join("archive/pc/mod").as_path()));
+        assert!(parked_archive.is_file());
+        assert!(InstallManifest::disabled_location(&cyberpunk_dir, "mod.zip", Path::new("r6/scripts/a.reds")).is_file());
+
+        set_mod_enabled(&cyberpunk_dir, "mod.zip", true).await;
+        assert_eq!(status(&cyberpunk_dir, "mod.zip"), ModInstallStatus::Installed);
+        assert_eq!(fs::read_to_string(cyberpunk_dir.join("r6/scripts/a.reds")).unwrap(), "a");
+        assert!(!parked_archive.exists());
+    }
+
+    #[tokio::test]
+    async fn should_not_enable_over_files_of_other_mods() {
+        let cyberpunk_dir = game_dir("enable_blocked");
+        let mut manifest = InstallManifest::default();
+        manifest.insert(install(&cyberpunk_dir, "mod.zip", &[("r6/scripts/a.reds", "a", None)]));
+        manifest.save(&cyberpunk_dir).unwrap();
+        set_mod_enabled(&cyberpunk_dir, "mod.zip", false).await;
+        // another mod installs the same file while the first one is disabled
+        let mut manifest = InstallManifest::load(&cyberpunk_dir).unwrap();
+        manifest.insert(install(&cyberpunk_dir, "other.zip", &[("r6/scripts/a.reds", "other", None)]));
+        manifest.save(&cyberpunk_dir).unwrap();
+
+        set_mod_enabled(&cyberpunk_dir, "mod.zip", true).await;
+        assert_eq!(status(&cyberpunk_dir, "mod.zip"), ModInstallStatus::Disabled);
+        assert_eq!(fs::read_to_string(cyberpunk_dir.join("r6/scripts/a.reds")).unwrap(), "other");
+        assert!(InstallManifest::disabled_location(&cyberpunk_dir, "mod.zip", Path::new("r6/scripts/a.reds")).is_file());
+    }
+
+    /// Install `contents` at `path` the way an install does, what was there goes into the mod's backups
+    fn install_over(cyberpunk_dir: &Path, manifest: &mut InstallManifest, mod_name: &str, path: &str, contents: &str) {
+        let dest_path = cyberpunk_dir.join(path);
+        let previous_owner = manifest.owners().owner_of(Path::new(path)).map(|owner| owner.to_string());
+        let backed_up = dest_path.is_file();
+        if backed_up {
+            let backup_path = BackupStore::for_mod(cyberpunk_dir, mod_name).backup_path(Path::new(path));
+            fs::create_dir_all(backup_path.parent().unwrap()).unwrap();
+            fs::rename(&dest_path, backup_path).unwrap();
+        }
+        let mut installed_mod = install(cyberpunk_dir, mod_name, &[(path, contents, previous_owner.as_deref())]);
+        installed_mod.files[0].backed_up = backed_up;
+        manifest.insert(installed_mod);
+        manifest.save(cyberpunk_dir).unwrap();
+    }
+
+    async fn uninstall(cyberpunk_dir: &Path, mod_name: &str) {
+        let mut handler = handler(cyberpunk_dir, mod_name);
+        handler.app.lock().await.mod_popup.as_mut().unwrap().plan_confirmed = true;
+        handler.uninstall_mod().await.unwrap();
+    }
+
+    /// The plan an uninstall shows before anything is changed
+    async fn plan_of_uninstall(cyberpunk_dir: &Path, mod_name: &str) -> OperationPlan {
+        let mut handler = handler(cyberpunk_dir, mod_name);
+        handler.uninstall_mod().await.unwrap();
+        let plan_preview = handler.app.lock().await.plan_preview.take();
+        plan_preview.unwrap().plan
+    }
+
+    fn game_ini(cyberpunk_dir: &Path) -> String {
+        fs::read_to_string(cyberpunk_dir.join("bin/x64/game.ini")).unwrap()
+    }
+
+    fn game_ini_of(cyberpunk_dir: &Path, mod_name: &str) -> InstalledFile {
+        InstallManifest::load(cyberpunk_dir).unwrap().get(mod_name).unwrap().files[0].clone()
+    }
+
+    #[tokio::test]
+    async fn should_hand_backups_to_the_mod_that_overwrote_the_uninstalled_one() {
+        let cyberpunk_dir = game_dir("uninstall_overwritten");
+        fs::create_dir_all(cyberpunk_dir.join("bin/x64")).unwrap();
+        fs::write(cyberpunk_dir.join("bin/x64/game.ini"), "original").unwrap();
+        let mut manifest = InstallManifest::default();
+        install_over(&cyberpunk_dir, &mut manifest, "a.zip", "bin/x64/game.ini", "a");
+        install_over(&cyberpunk_dir, &mut manifest, "b.zip", "bin/x64/game.ini", "b");
+        assert_eq!(game_ini_of(&cyberpunk_dir, "b.zip").previous_owner.as_deref(), Some("a.zip"));
+
+        let plan = plan_of_uninstall(&cyberpunk_dir, "a.zip").await;
+        assert_eq!(plan.reassigned.len(), 1);
+        assert_eq!(plan.reassigned[0].owner.as_deref(), Some("b.zip"));
+        assert!(plan.remove.is_empty());
+        assert_eq!(game_ini(&cyberpunk_dir), "b");
+        uninstall(&cyberpunk_dir, "a.zip").await;
+        assert_eq!(game_ini(&cyberpunk_dir), "b");
+        let successor_file = game_ini_of(&cyberpunk_dir, "b.zip");
+        assert_eq!(successor_file.previous_owner, None);
+        assert!(successor_file.backed_up);
+        let successor_backup = BackupStore::for_mod(&cyberpunk_dir, "b.zip").backup_path(Path::new("bin/x64/game.ini"));
+        assert_eq!(fs::read_to_string(successor_backup).unwrap(), "original");
+        assert!(!BackupStore::for_mod(&cyberpunk_dir, "a.zip").backup_path(Path::new("bin/x64/game.ini")).exists());
+
+        uninstall(&cyberpunk_dir, "b.zip").await;
+        assert_eq!(game_ini(&cyberpunk_dir), "original");
+        assert!(InstallManifest::load(&cyberpunk_dir).unwrap().mods.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_leave_a_disabled_successor_the_original() {
+        let cyberpunk_dir = game_dir("uninstall_disabled_successor");
+        fs::create_dir_all(cyberpunk_dir.join("bin/x64")).unwrap();
+        fs::write(cyberpunk_dir.join("bin/x64/game.ini"), "original").unwrap();
+        let mut manifest = InstallManifest::default();
+        install_over(&cyberpunk_dir, &mut manifest, "a.zip", "bin/x64/game.ini", "a");
+        install_over(&cyberpunk_dir, &mut manifest, "b.zip", "bin/x64/game.ini", "b");
+        set_mod_enabled(&cyberpunk_dir, "b.zip", false).await;
+        assert_eq!(game_ini(&cyberpunk_dir), "a");
+
+        uninstall(&cyberpunk_dir, "a.zip").await;
+        assert_eq!(game_ini(&cyberpunk_dir), "original");
+        let successor_file = game_ini_of(&cyberpunk_dir, "b.zip");
+        assert_eq!(successor_file.previous_owner, None);
+        assert!(successor_file.backed_up);
+
+        // enabling it again replaces the original, which it now keeps
+        set_mod_enabled(&cyberpunk_dir, "b.zip", true).await;
+        assert_eq!(game_ini(&cyberpunk_dir), "b");
+        uninstall(&cyberpunk_dir, "b.zip").await;
+        assert_eq!(game_ini(&cyberpunk_dir), "original");
+    }
+
+    #[tokio::test]
+    async fn should_keep_the_original_of_files_changed_after_install() {
+        let cyberpunk_dir = game_dir("uninstall_changed");
+        fs::create_dir_all(cyberpunk_dir.join("bin/x64")).unwrap();
+        fs::write(cyberpunk_dir.join("bin/x64/game.ini"), "original").unwrap();
+        let mut manifest = InstallManifest::default();
+        install_over(&cyberpunk_dir, &mut manifest, "a.zip", "bin/x64/game.ini", "a");
+        fs::write(cyberpunk_dir.join("bin/x64/game.ini"), "edited by hand").unwrap();
+
+        let plan = plan_of_uninstall(&cyberpunk_dir, "a.zip").await;
+        assert_eq!(plan.kept.len(), 1);
+        assert!(plan.remove.is_empty() && plan.restore.is_empty());
+        uninstall(&cyberpunk_dir, "a.zip").await;
+        assert_eq!(game_ini(&cyberpunk_dir), "edited by hand");
+        let kept_path = BackupStore::kept_for_mod(&cyberpunk_dir, "a.zip").backup_path(Path::new("bin/x64/game.ini"));
+        assert_eq!(fs::read_to_string(kept_path).unwrap(), "original");
+        assert!(InstallManifest::load(&cyberpunk_dir).unwrap().get("a.zip").is_none());
+    }
+
+    #[tokio::test]
+    async fn should_uninstall_disabled_mods() {
+        let cyberpunk_dir = game_dir("uninstall_disabled");
+        let mut installed_mod = install(&cyberpunk_dir, "mod.zip", &[("bin/x64/game.ini", "modded", None)]);
+        // the mod replaced a file of the game
+        installed_mod.files[0].backed_up = true;
+        let backup_path = BackupStore::for_mod(&cyberpunk_dir, "mod.zip").backup_path(Path::new("bin/x64/game.ini"));
+        fs::create_dir_all(backup_path.parent().unwrap()).unwrap();
+        fs::write(&backup_path, "original").unwrap();
+        let mut manifest = InstallManifest::default();
+        manifest.insert(installed_mod);
+        manifest.save(&cyberpunk_dir).unwrap();
+        set_mod_enabled(&cyberpunk_dir, "mod.zip", false).await;
+        assert_eq!(fs::read_to_string(cyberpunk_dir.join("bin/x64/game.ini")).unwrap(), "original");
+
+        let mut handler = handler(&cyberpunk_dir, "mod.zip");
+        handler.app.lock().await.mod_popup.as_mut().unwrap().plan_confirmed = true;
+        handler.uninstall_mod().await.unwrap();
+        assert_eq!(status(&cyberpunk_dir, "mod.zip"), ModInstallStatus::NotInstalled);
+        assert_eq!(fs::read_to_string(cyberpunk_dir.join("bin/x64/game.ini")).unwrap(), "original");
+        assert!(!InstallManifest::disabled_mod_dir(&cyberpunk_dir, "mod.zip").exists());
+        assert!(!backup_path.exists());
+    }
+
+    #[tokio::test]
+    async fn should_show_a_batch_as_one_plan_before_running_it() {
+        let cyberpunk_dir = game_dir("batch_plan");
+        let mut manifest = InstallManifest::default();
+        manifest.insert(install(&cyberpunk_dir, "a.zip", &[("r6/scripts/a.reds", "a", None)]));
+        manifest.insert(install(&cyberpunk_dir, "b.zip", &[("r6/scripts/b.reds", "b", None)]));
+        manifest.save(&cyberpunk_dir).unwrap();
+        let mod_names = vec!["a.zip".to_string(), "b.zip".to_string(), "c.zip".to_string()];
+
+        let mut handler = handler(&cyberpunk_dir, "a.zip");
+        handler.run_batch(BatchOperation::Uninstall, mod_names.clone(), false).await.unwrap();
+        let plan_preview = handler.app.lock().await.plan_preview.take().unwrap();
+        // uninstalls run in reverse, mods go before the frameworks they need
+        assert_eq!(plan_preview.plan.steps, vec!["Skip c.zip, not installed", "Uninstall b.zip", "Uninstall a.zip"]);
+        assert_eq!(plan_preview.plan.remove.len(), 2);
+        assert!(matches!(plan_preview.confirm, IoEvent::ApplyBatch(BatchOperation::Uninstall, _)));
+        // nothing changes before the plan is confirmed
+        assert_eq!(handler.app.lock().await.mod_popup.as_ref().unwrap().mod_name, "a.zip");
+        assert_eq!(status(&cyberpunk_dir, "a.zip"), ModInstallStatus::Installed);
+        assert!(cyberpunk_dir.join("r6/scripts/b.reds").is_file());
+
+        handler.run_batch(BatchOperation::Uninstall, mod_names, true).await.unwrap();
+        assert!(InstallManifest::load(&cyberpunk_dir).unwrap().mods.is_empty());
+        assert!(!cyberpunk_dir.join("r6/scripts/a.reds").exists());
+        assert!(!cyberpunk_dir.join("r6/scripts/b.reds").exists());
+    }
+
+    #[tokio::test]
+    async fn should_not_plan_a_switch_to_a_profile_that_matches() {
+        let cyberpunk_dir = game_dir("switch_matching");
+        let mut manifest = InstallManifest::default();
+        manifest.insert(install(&cyberpunk_dir, "a.zip", &[("r6/scripts/a.reds", "a", None)]));
+        manifest.save(&cyberpunk_dir).unwrap();
+        let mut handler = handler(&cyberpunk_dir, "a.zip");
+        {
+            let mut app = handler.app.lock().await;
+            app.mod_folder = Some(cyberpunk_dir.clone());
+            app.state.profiles.items = vec![Profile::capture("testing".to_string(), &manifest, vec![])];
+        }
+
+        handler.switch_profile("testing".to_string(), false).await.unwrap();
+        let app = handler.app.lock().await;
+        assert!(app.plan_preview.is_none());
+        assert_eq!(app.active_profile.as_deref(), Some("testing"));
+        assert!(!modlist_path(&cyberpunk_dir).exists());
+    }
+
+    #[test]
+    fn should_only_repair_missing_and_modified_files() {
+        let cyberpunk_dir = game_dir("repair");
+        let extracted_dir = cyberpunk_dir.join("extracted");
+        let files = ["archive/pc/mod/intact.archive", "archive/pc/mod/modified.archive", "archive/pc/mod/missing.archive", "archive/pc/mod/taken.archive"];
+        let mut mod_files = vec![];
+        for path in files {
+            let source = extracted_dir.join(path);
+            fs::create_dir_all(source.parent().unwrap()).unwrap();
+            fs::write(&source, path).unwrap();
+            mod_files.push(ModFile { source, destination: PathBuf::from(path) });
+        }
+        let mut manifest = InstallManifest::default();
+        manifest.insert(install(&cyberpunk_dir, "mod.zip", &files.map(|path| (path, path, None))));
+        // another mod overwrote one of the files since, it is theirs now
+        manifest.insert(install(&cyberpunk_dir, "other.zip", &[(files[3], "other", Some("mod.zip"))]));
+        manifest.save(&cyberpunk_dir).unwrap();
+        fs::write(cyberpunk_dir.join(files[1]), "changed").unwrap();
+        fs::remove_file(cyberpunk_dir.join(files[2])).unwrap();
+        let intact_modified = fs::metadata(cyberpunk_dir.join(files[0])).unwrap().modified().unwrap();
+
+        let progress = ProgressHandle::default();
+        let installed_mod = manifest.get("mod.zip").unwrap().clone();
+        let (plan, journal) = plan_repair(&cyberpunk_dir, &manifest, installed_mod, &mod_files, &progress).unwrap();
+        let paths = |planned_files: &[PlannedFile]| planned_files.iter().map(|planned_file| planned_file.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&plan.create), vec![PathBuf::from(files[2])]);
+        assert_eq!(paths(&plan.overwrite), vec![PathBuf::from(files[1])]);
+        assert_eq!(paths(&plan.set_aside), vec![PathBuf::from(files[1])]);
+        journal.run_cancellable(&cyberpunk_dir, &progress).unwrap();
+
+        for path in &files[..3] {
+            assert_eq!(fs::read_to_string(cyberpunk_dir.join(path)).unwrap(), *path);
+        }
+        assert_eq!(fs::read_to_string(cyberpunk_dir.join(files[3])).unwrap(), "other");
+        assert_eq!(fs::metadata(cyberpunk_dir.join(files[0])).unwrap().modified().unwrap(), intact_modified);
+        let manifest = InstallManifest::load(&cyberpunk_dir).unwrap();
+        let repaired_file = &manifest.get("mod.zip").unwrap().files[1];
+        assert_eq!(repaired_file.hash, hash_file(&cyberpunk_dir.join(files[1])).unwrap());
+        // the changed file is not lost
+        let kept_path = BackupStore::kept_for_mod(&cyberpunk_dir, "mod.zip").backup_path(Path::new(files[1]));
+        assert_eq!(fs::read_to_string(kept_path).unwrap(), "changed");
+    }
+}
@@ -0,0 +1,446 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use compress_tools::{list_archive_files, ArchiveContents, ArchiveIterator};
+use eyre::{eyre, Result};
+use log::warn;
+use walkdir::WalkDir;
+
+use crate::constants::GAME_ROOT_FOLDERS;
+
+use super::{
+    paths::{mod_archive_dir, normalize_archive_path, normalize_relative_path, CaseResolver},
+    progress::{ProgressHandle, ProgressPhase},
+};
+
+/// A file inside an extracted mod together with where it goes in the Cyberpunk folder
+#[derive(Debug, Clone)]
+pub struct ModFile {
+    pub source: PathBuf,
+    /// Path relative to the Cyberpunk folder
+    pub destination: PathBuf,
+}
+
+/// Extract a mod archive into `destination` one entry at a time, reporting every extracted file and
+/// stopping between entries once the operation is cancelled. Entries that would be unpacked outside
+/// of `destination` fail the extraction. Returns the bytes written
+pub fn extract_archive(mod_path: &Path, destination: &Path, progress: &ProgressHandle) -> Result<u64> {
+    // the number of files is only known once the archive was read to its end
+    progress.phase(ProgressPhase::Extract, 0, 0);
+    let mut entries = ArchiveIterator::from_read(File::open(mod_path)?)?;
+    let mut bytes_total = 0;
+    // the file of the current entry and the bytes written to it, folders have none
+    let mut current: Option<(File, u64)> = None;
+    for contents in &mut entries {
+        match contents {
+            ArchiveContents::StartOfEntry(path, _) => {
+                progress.check_cancelled()?;
+                let relative_path =
+                    normalize_archive_path(&path).ok_or_else(|| eyre!("{} would be unpacked outside of the mod folder", path))?;
+                // folders are listed as entries of their own
+                if path.ends_with('/') || path.ends_with('\\') || relative_path.as_os_str().is_empty() {
+                    continue;
+                }
+                let target = destination.join(relative_path);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                current = Some((File::create(target)?, 0));
+            }
+            ArchiveContents::DataChunk(chunk) => {
+                if let Some((file, bytes)) = current.as_mut() {
+                    file.write_all(&chunk)?;
+                    *bytes += chunk.len() as u64;
+                }
+            }
+            ArchiveContents::EndOfEntry => {
+                if let Some((_, bytes)) = current.take() {
+                    bytes_total += bytes;
+                    progress.file_done(bytes);
+                }
+            }
+            ArchiveContents::Err(err) => return Err(err.into()),
+        }
+    }
+    entries.close()?;
+    Ok(bytes_total)
+}
+
+/// The files inside a mod archive, read from its index without extracting anything
+pub fn list_mod_files(mod_path: &Path) -> Result<Vec<PathBuf>> {
+    let source = File::open(mod_path)?;
+    list_archive_files(source)?
+        .iter()
+        // folders are listed as entries of their own
+        .filter(|path| !path.ends_with('/') && !path.ends_with('\\'))
+        .map(|path| normalize_archive_path(path).ok_or_else(|| eyre!("{} would be unpacked outside of the mod folder", path)))
+        .collect()
+}
+
+/// Whether the files of an archive look like a mod: they hold a folder of the Cyberpunk folder,
+/// a FOMOD installer or `.archive` files
+pub fn looks_like_mod(paths: &[PathBuf]) -> bool {
+    paths.iter().any(|path| {
+        let is_archive = path.to_string_lossy().to_lowercase().ends_with(".archive");
+        let has_mod_folder = path.parent().into_iter().flat_map(|parent| parent.iter()).any(|folder| {
+            let folder = folder.to_string_lossy().to_lowercase();
+            folder == "fomod" || GAME_ROOT_FOLDERS.contains(&folder.as_str())
+        });
+        is_archive || has_mod_folder
+    })
+}
+
+/// Where the game folder starts inside a mod archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveRoot {
+    /// Relative to the extraction root, empty when the archive is laid out like the game folder
+    Detected(PathBuf),
+    /// Several folders look like a game folder, the user has to pick one
+    Ambiguous(Vec<PathBuf>),
+}
+
+/// Every file of an extracted mod as (path on disk, normalized path relative to the extraction root)
+fn extracted_files(temp_mod_path: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut files = vec![];
+    for entry in WalkDir::new(temp_mod_path) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            // entries packed with `\` separators end up as a single file name on Linux
+            match normalize_relative_path(entry.path().strip_prefix(temp_mod_path)?) {
+                Some(relative_path) => files.push((entry.path().to_path_buf(), relative_path)),
+                None => warn!("⚠️ Skipping {}, it would be installed outside of the Cyberpunk folder", entry.path().to_string_lossy()),
+            }
+        }
+    }
+    // a stable order decides which file wins when paths collide
+    files.sort();
+    Ok(files)
+}
+
+fn is_game_root_folder(name: &str) -> bool {
+    GAME_ROOT_FOLDERS.iter().any(|folder| folder.eq_ignore_ascii_case(name))
+}
+
+/// Find the folder of the archive that holds the known top level game folders, so wrappers
+/// like `ModName-v1.2/` or `Cyberpunk 2077/` are stripped
+pub fn detect_archive_root(relative_paths: &[PathBuf]) -> ArchiveRoot {
+    let mut candidates: Vec<PathBuf> = vec![];
+    for relative_path in relative_paths {
+        let components: Vec<_> = relative_path.components().collect();
+        // the last component is the file itself, only folders can be game folders
+        for (index, component) in components.iter().enumerate().take(components.len().saturating_sub(1)) {
+            let name = component.as_os_str().to_string_lossy();
+            // CET keeps its scripts in a `mods` folder too, only REDmod folders with an info.json count
+            let is_redmod_folder = name.eq_ignore_ascii_case("mods")
+                && components.len() == index + 3
+                && components[index + 2].as_os_str().eq_ignore_ascii_case("info.json");
+            if is_redmod_folder || (!name.eq_ignore_ascii_case("mods") && is_game_root_folder(&name)) {
+                let candidate: PathBuf = components[..index].iter().collect();
+                if !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+                break;
+            }
+        }
+    }
+    // folders named like game folders deeper inside a detected root (e.g. cyber_engine_tweaks/mods) don't count.
+    // The top of the archive is a prefix of every folder, it only competes with the others
+    let roots: Vec<PathBuf> = candidates
+        .iter()
+        .filter(|candidate| {
+            !candidates
+                .iter()
+                .any(|other| other != *candidate && !other.as_os_str().is_empty() && candidate.starts_with(other))
+        })
+        .cloned()
+        .collect();
+    match roots.len() {
+        0 => ArchiveRoot::Detected(PathBuf::new()),
+        1 => ArchiveRoot::Detected(roots[0].clone()),
+        _ => ArchiveRoot::Ambiguous(roots),
+    }
+}
+
+/// Map every file below `archive_root` of an extracted mod to its destination in the Cyberpunk folder
+pub fn collect_mod_files(temp_mod_path: &Path, archive_root: &Path) -> Result<Vec<ModFile>> {
+    Ok(map_destinations(extracted_files(temp_mod_path)?, archive_root)
+        .into_iter()
+        .map(|(source, destination)| ModFile { source, destination })
+        .collect())
+}
+
+/// The destinations `collect_mod_files` will find once the archive is extracted, from its listing
+pub fn listed_destinations(listed_files: &[PathBuf], archive_root: &Path) -> Vec<PathBuf> {
+    let files = listed_files.iter().map(|path| ((), path.clone())).collect();
+    map_destinations(files, archive_root)
+        .into_iter()
+        .map(|(_, destination)| destination)
+        .collect()
+}
+
+/// Pair every file below `archive_root` with its destination, `files` hold whatever identifies a
+/// file together with its normalized path relative to the extraction root
+fn map_destinations<T>(files: Vec<(T, PathBuf)>, archive_root: &Path) -> Vec<(T, PathBuf)> {
+    let files: Vec<(T, PathBuf)> = files
+        .into_iter()
+        .filter_map(|(source, relative_path)| {
+            relative_path
+                .strip_prefix(archive_root)
+                .ok()
+                .map(|relative_path| (source, relative_path.to_path_buf()))
+        })
+        // installer metadata, only the FOMOD installer itself reads it
+        .filter(|(_, relative_path)| {
+            !relative_path
+                .components()
+                .next()
+                .map(|component| component.as_os_str().eq_ignore_ascii_case("fomod"))
+                .unwrap_or_default()
+        })
+        .collect();
+    // mods that only ship .archive files are dropped straight into archive/pc/mod
+    let has_only_archive_files = !files.is_empty()
+        && files.iter().all(|(_, path)| path.extension().unwrap_or_default() == "archive");
+    files
+        .into_iter()
+        .map(|(source, relative_path)| {
+            let destination = if has_only_archive_files {
+                mod_archive_dir().join(relative_path.file_name().unwrap_or_default())
+            } else {
+                relative_path
+            };
+            (source, destination)
+        })
+        .collect()
+}
+
+/// Reuse the casing of folders and files that already exist in the Cyberpunk folder. Files of the
+/// archive that collide after that are skipped and returned next to the files to install
+pub fn resolve_destinations(cyberpunk_dir: &Path, mod_files: Vec<ModFile>) -> (Vec<ModFile>, Vec<ModFile>) {
+    let mut resolver = CaseResolver::new(cyberpunk_dir);
+    let mut destinations: HashSet<PathBuf> = HashSet::new();
+    let mut resolved_files: Vec<ModFile> = vec![];
+    let mut collisions: Vec<ModFile> = vec![];
+    for mod_file in mod_files {
+        let resolved_file = ModFile {
+            source: mod_file.source,
+            destination: resolver.resolve(&mod_file.destination),
+        };
+        if destinations.insert(resolved_file.destination.clone()) {
+            resolved_files.push(resolved_file);
+        } else {
+            warn!(
+                "⚠️ {} only differs in case from another file of the archive, skipping it",
+                resolved_file.source.to_string_lossy()
+            );
+            collisions.push(resolved_file);
+        }
+    }
+    (resolved_files, collisions)
+}
+
+/// Directories that would have to be created to place `relative_path` in the Cyberpunk folder
+pub fn missing_parent_dirs(cyberpunk_dir: &Path, relative_path: &Path) -> Vec<PathBuf> {
+    let mut missing = vec![];
+    let mut parent = relative_path.parent();
+    while let Some(dir) = parent {
+        if dir.as_os_str().is_empty() || cyberpunk_dir.join(dir).is_dir() {
+            break;
+        }
+        missing.push(dir.to_path_buf());
+        parent = dir.parent();
+    }
+    missing
+}
+
+/// Remove the given directories of the Cyberpunk folder if they are empty, deepest first
+pub fn remove_empty_dirs(cyberpunk_dir: &Path, dirs: &[PathBuf]) -> Result<()> {
+    let mut dirs = dirs.to_vec();
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    for dir in dirs {
+        let full_path = cyberpunk_dir.join(dir);
+        if full_path.is_dir() && full_path.read_dir()?.next().is_none() {
+            fs::remove_dir(&full_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Move a file, creating the destination directory and falling back to copy and delete
+/// when a plain rename is not possible
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Write a file through a temporary file next to it, so a crash never leaves a truncated file behind
+pub fn write_file_atomically(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    fs::write(&temp_path, contents)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::paths::normalize_archive_path;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(|path| normalize_archive_path(path).unwrap()).collect()
+    }
+
+    #[test]
+    fn should_tell_mods_from_other_archives() {
+        assert!(looks_like_mod(&paths(&["Wrapper/Bin/x64/plugin.dll"])));
+        assert!(looks_like_mod(&paths(&["loose.archive"])));
+        assert!(looks_like_mod(&paths(&["fomod/ModuleConfig.xml", "a/file.txt"])));
+        // a file named like a game folder is not one
+        assert!(!looks_like_mod(&paths(&["readme.txt", "docs/bin"])));
+    }
+
+    #[test]
+    fn should_keep_root_of_game_layout() {
+        let root = detect_archive_root(&paths(&["archive/pc/mod/a.archive", "r6/scripts/a.reds"]));
+        assert_eq!(root, ArchiveRoot::Detected(PathBuf::new()));
+    }
+
+    #[test]
+    fn should_strip_wrapper_folders() {
+        let root = detect_archive_root(&paths(&[
+            "ModName-v1.2/Cyberpunk 2077/bin/x64/plugins/cyber_engine_tweaks/mods/my_mod/init.lua",
+            "ModName-v1.2/Cyberpunk 2077/Archive/pc/mod/a.archive",
+            "ModName-v1.2/readme.txt",
+        ]));
+        assert_eq!(root, ArchiveRoot::Detected(normalize_archive_path("ModName-v1.2/Cyberpunk 2077").unwrap()));
+    }
+
+    #[test]
+    fn should_report_ambiguous_roots() {
+        let root = detect_archive_root(&paths(&["Main/archive/pc/mod/a.archive", "Optional/r6/scripts/b.reds"]));
+        assert_eq!(root, ArchiveRoot::Ambiguous(paths(&["Main", "Optional"])));
+        let root = detect_archive_root(&paths(&["archive/pc/mod/a.archive", "Optional/archive/pc/mod/b.archive"]));
+        assert_eq!(root, ArchiveRoot::Ambiguous(paths(&["", "Optional"])));
+    }
+
+    #[test]
+    fn should_skip_files_only_differing_in_case() {
+        let mod_files = ["R6/Scripts/a.reds", "r6/scripts/A.reds", "r6/scripts/b.reds"]
+            .iter()
+            .map(|path| ModFile {
+                source: PathBuf::from(path),
+                destination: normalize_archive_path(path).unwrap(),
+            })
+            .collect();
+        let cyberpunk_dir = std::env::temp_dir().join("cyberpunk_mod_manager_tests").join("case_collisions");
+        let (resolved_files, collisions) = resolve_destinations(&cyberpunk_dir, mod_files);
+        let destinations: Vec<PathBuf> = resolved_files.into_iter().map(|mod_file| mod_file.destination).collect();
+        assert_eq!(destinations, paths(&["R6/Scripts/a.reds", "R6/Scripts/b.reds"]));
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].source, PathBuf::from("r6/scripts/A.reds"));
+    }
+
+    #[test]
+    fn should_map_listed_files_like_extracted_ones() {
+        let listed = paths(&["Wrapper/r6/scripts/a.reds", "Wrapper/fomod/info.xml", "readme.txt"]);
+        assert_eq!(listed_destinations(&listed, Path::new("Wrapper")), paths(&["r6/scripts/a.reds"]));
+        let listed = paths(&["textures/a.archive", "b.archive"]);
+        assert_eq!(listed_destinations(&listed, Path::new("")), paths(&["archive/pc/mod/a.archive", "archive/pc/mod/b.archive"]));
+    }
+
+    #[test]
+    fn should_not_mistake_cet_mods_folder_for_redmod() {
+        let root = detect_archive_root(&paths(&["cyber_engine_tweaks/mods/my_mod/init.lua"]));
+        assert_eq!(root, ArchiveRoot::Detected(PathBuf::new()));
+        let root = detect_archive_root(&paths(&["MyRedmod/mods/my_redmod/info.json", "MyRedmod/mods/my_redmod/archives/a.archive"]));
+        assert_eq!(root, ArchiveRoot::Detected(PathBuf::from("MyRedmod")));
+    }
+
+    #[test]
+    fn should_replace_files_atomically() {
+        let root = std::env::temp_dir().join("cyberpunk_mod_manager_tests").join("atomic_write");
+        if root.exists() {
+            fs::remove_dir_all(&root).unwrap();
+        }
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("install_manifest.json");
+        write_file_atomically(&path, "old").unwrap();
+        write_file_atomically(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+    }
+
+    /// A tar archive holding `files`, libarchive reads it like any other mod archive
+    fn tar(root: &Path, files: &[(&str, &str)]) -> PathBuf {
+        let mut bytes = vec![];
+        for (path, contents) in files {
+            let mut header = [0u8; 512];
+            header[..path.len()].copy_from_slice(path.as_bytes());
+            header[100..107].copy_from_slice(b"0000644");
+            header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+            header[136..147].copy_from_slice(b"00000000000");
+            header[148..156].copy_from_slice(b"        ");
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[263..265].copy_from_slice(b"00");
+            let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+            header[148..155].copy_from_slice(format!("{:06o}\0", checksum).as_bytes());
+            bytes.extend(header);
+            bytes.extend(contents.as_bytes());
+            bytes.resize(bytes.len().div_ceil(512) * 512, 0);
+        }
+        bytes.resize(bytes.len() + 1024, 0);
+        let archive_path = root.join("mod.tar");
+        fs::write(&archive_path, bytes).unwrap();
+        archive_path
+    }
+
+    fn extraction_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join("cyberpunk_mod_manager_tests").join(name);
+        if root.exists() {
+            fs::remove_dir_all(&root).unwrap();
+        }
+        fs::create_dir_all(root.join("extracted")).unwrap();
+        root
+    }
+
+    #[test]
+    fn should_extract_entry_by_entry() {
+        let root = extraction_root("extract_entries");
+        let archive_path = tar(&root, &[("archive/pc/mod/a.archive", "abc"), ("r6/scripts/a.reds", "de")]);
+        let progress = ProgressHandle::default();
+        assert_eq!(extract_archive(&archive_path, &root.join("extracted"), &progress).unwrap(), 5);
+        assert_eq!(fs::read_to_string(root.join("extracted/r6/scripts/a.reds")).unwrap(), "de");
+        assert_eq!(progress.get().unwrap().files_done, 2);
+    }
+
+    #[test]
+    fn should_refuse_entries_leaving_the_destination() {
+        let root = extraction_root("extract_unsafe");
+        let archive_path = tar(&root, &[("../evil.reds", "evil")]);
+        assert!(extract_archive(&archive_path, &root.join("extracted"), &ProgressHandle::default()).is_err());
+        assert!(!root.join("evil.reds").exists());
+    }
+
+    #[test]
+    fn should_stop_extracting_once_cancelled() {
+        let root = extraction_root("extract_cancelled");
+        let archive_path = tar(&root, &[("archive/pc/mod/a.archive", "abc")]);
+        let progress = ProgressHandle::default();
+        progress.cancel();
+        assert!(extract_archive(&archive_path, &root.join("extracted"), &progress).is_err());
+        assert_eq!(fs::read_dir(root.join("extracted")).unwrap().count(), 0);
+    }
+}
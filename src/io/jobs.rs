@@ -0,0 +1,169 @@
+use std::sync::{Arc, Mutex};
+
+use log::error;
+use tokio::{runtime::Handle, sync::mpsc::Receiver, task::JoinHandle};
+
+use crate::app::App;
+
+use super::{handler::IoAsyncHandler, IoEvent};
+
+/// Finished and failed jobs kept for the job list, the oldest are dropped first
+const MAX_DONE_JOBS: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Finished,
+    Failed(String),
+}
+
+impl JobState {
+    pub fn to_str(&self) -> &str {
+        match self {
+            JobState::Queued => "Queued",
+            JobState::Running => "Running",
+            JobState::Finished => "Finished",
+            JobState::Failed(_) => "Failed",
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        matches!(self, JobState::Finished | JobState::Failed(_))
+    }
+}
+
+/// A dispatched `IoEvent` as it goes through the job queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: u64,
+    pub description: String,
+    /// Runs after every serialized job dispatched before it, see `IoEvent::is_serialized`
+    pub serialized: bool,
+    pub state: JobState,
+}
+
+/// A dispatched `IoEvent` with the id its job got in the `JobList`
+pub type JobEvent = (u64, IoEvent);
+
+/// The jobs of this session, shared with the UI so it can list them without waiting for the app lock
+#[derive(Debug, Clone, Default)]
+pub struct JobList {
+    jobs: Arc<Mutex<Vec<Job>>>,
+}
+
+impl JobList {
+    pub fn all(&self) -> Vec<Job> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    pub fn add(&self, description: String, serialized: bool) -> u64 {
+        let mut jobs = self.jobs.lock().unwrap();
+        let id = jobs.last().map(|job| job.id + 1).unwrap_or(1);
+        jobs.push(Job {
+            id,
+            description,
+            serialized,
+            state: JobState::Queued,
+        });
+        let done = jobs.iter().filter(|job| job.state.is_done()).count();
+        if done > MAX_DONE_JOBS {
+            let mut to_drop = done - MAX_DONE_JOBS;
+            jobs.retain(|job| {
+                let drop = to_drop > 0 && job.state.is_done();
+                if drop {
+                    to_drop -= 1;
+                }
+                !drop
+            });
+        }
+        id
+    }
+
+    pub fn set_state(&self, id: u64, state: JobState) {
+        if let Some(job) = self.jobs.lock().unwrap().iter_mut().find(|job| job.id == id) {
+            job.state = state;
+        }
+    }
+
+    /// Whether a serialized job is waiting or running, the UI leaves the app alone until there is none
+    pub fn serialized_pending(&self) -> bool {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|job| job.serialized && !job.state.is_done())
+    }
+}
+
+/// Run every dispatched `IoEvent` as a job on the blocking thread pool. Jobs that change the game
+/// folder or the settings run one after another in the order they were dispatched, everything
+/// else runs as soon as it arrives
+pub async fn run_jobs(app: Arc<tokio::sync::Mutex<App>>, mut io_rx: Receiver<JobEvent>) {
+    let jobs = app.lock().await.jobs.clone();
+    let mut last_serialized: Option<JoinHandle<()>> = None;
+    while let Some((id, io_event)) = io_rx.recv().await {
+        let serialized = io_event.is_serialized();
+        let previous = if serialized { last_serialized.take() } else { None };
+        let (app, jobs) = (app.clone(), jobs.clone());
+        let job = tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+            jobs.set_state(id, JobState::Running);
+            let handler_app = app.clone();
+            // handlers do blocking filesystem work, keep it off the threads driving the UI
+            let result = tokio::task::spawn_blocking(move || {
+                let mut handler = IoAsyncHandler::new(handler_app);
+                Handle::current().block_on(handler.handle_io_event(io_event))
+            })
+            .await;
+            let state = match result {
+                Ok(Ok(())) => JobState::Finished,
+                Ok(Err(err)) => JobState::Failed(err.to_string()),
+                Err(err) => {
+                    error!("🚫 Job {} crashed: {:?}", id, err);
+                    JobState::Failed("Crashed".to_string())
+                }
+            };
+            let mut app = app.lock().await;
+            jobs.set_state(id, state);
+            if serialized {
+                app.progress.finish();
+            }
+            if !jobs.serialized_pending() {
+                app.loaded();
+            }
+        });
+        if serialized {
+            last_serialized = Some(job);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_track_pending_serialized_jobs() {
+        let jobs = JobList::default();
+        let scan = jobs.add("Load mods".to_string(), false);
+        let install = jobs.add("Install mod".to_string(), true);
+        assert!(jobs.serialized_pending());
+        jobs.set_state(install, JobState::Failed("Cancelled".to_string()));
+        assert!(!jobs.serialized_pending());
+        jobs.set_state(scan, JobState::Running);
+        assert_eq!(jobs.all()[0].state, JobState::Running);
+
+        for _ in 0..MAX_DONE_JOBS + 5 {
+            let id = jobs.add("Save settings".to_string(), true);
+            jobs.set_state(id, JobState::Finished);
+        }
+        jobs.add("Save settings".to_string(), true);
+        let all = jobs.all();
+        assert_eq!(all.iter().filter(|job| job.state.is_done()).count(), MAX_DONE_JOBS);
+        // jobs that are not done are never dropped
+        assert_eq!(all[0].id, scan);
+    }
+}
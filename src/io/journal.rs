@@ -0,0 +1,266 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::Result;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{JOURNAL_FILE_NAME, STAGING_DIR_NAME};
+
+use super::{
+    backup::BackupStore,
+    installer::{move_file, remove_empty_dirs, write_file_atomically},
+    manifest::{unix_timestamp, InstallManifest, InstalledMod},
+    paths::portable_paths,
+    progress::ProgressHandle,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalKind {
+    Install,
+    Uninstall,
+    Repair,
+    Disable,
+    Enable,
+}
+
+impl JournalKind {
+    pub fn to_str(&self) -> &str {
+        match self {
+            JournalKind::Install => "install",
+            JournalKind::Uninstall => "uninstall",
+            JournalKind::Repair => "repair",
+            JournalKind::Disable => "disable",
+            JournalKind::Enable => "enable",
+        }
+    }
+}
+
+/// A single step of a transaction. Every change to the Cyberpunk folder is expressed as a move,
+/// so a step can always be told apart as done or not done by looking at where the file is
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl FileMove {
+    pub fn new(from: PathBuf, to: PathBuf) -> Self {
+        Self { from, to }
+    }
+
+    fn apply(&self) -> Result<()> {
+        if self.from.exists() {
+            debug!("🚀 Moving {} to {}", self.from.to_string_lossy(), self.to.to_string_lossy());
+            move_file(&self.from, &self.to)?;
+        }
+        Ok(())
+    }
+
+    fn undo(&self) -> Result<()> {
+        if self.to.exists() && !self.from.exists() {
+            debug!("🚀 Moving {} back to {}", self.to.to_string_lossy(), self.from.to_string_lossy());
+            move_file(&self.to, &self.from)?;
+        }
+        Ok(())
+    }
+}
+
+/// What has to happen to the install manifest once all moves went through
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ManifestChange {
+    Insert(InstalledMod),
+    Remove(String),
+    /// Remove a mod and save the mods that took over its files
+    Reassign(String, Vec<InstalledMod>),
+}
+
+/// A write ahead journal for an install or uninstall. It is saved before the first file is
+/// touched and deleted after the manifest is updated, so finding one on startup means the
+/// manager was interrupted halfway through
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Journal {
+    pub kind: JournalKind,
+    pub mod_name: String,
+    pub started_at: u64,
+    pub moves: Vec<FileMove>,
+    pub manifest_change: ManifestChange,
+    /// Directories the mod created in the Cyberpunk folder, removed again once they are empty
+    #[serde(default, with = "portable_paths")]
+    pub created_dirs: Vec<PathBuf>,
+}
+
+impl Journal {
+    pub fn new(kind: JournalKind, mod_name: String, manifest_change: ManifestChange) -> Self {
+        Self {
+            kind,
+            mod_name,
+            started_at: unix_timestamp(),
+            moves: vec![],
+            manifest_change,
+            created_dirs: vec![],
+        }
+    }
+
+    pub fn journal_path(cyberpunk_dir: &Path) -> PathBuf {
+        InstallManifest::data_dir(cyberpunk_dir).join(JOURNAL_FILE_NAME)
+    }
+
+    /// Where new files are staged before an install and removed files are parked during an uninstall
+    pub fn staging_dir(cyberpunk_dir: &Path, mod_name: &str) -> PathBuf {
+        InstallManifest::data_dir(cyberpunk_dir)
+            .join(STAGING_DIR_NAME)
+            .join(mod_name)
+    }
+
+    /// Drop the staged files of a mod, for operations that stop before their journal is run
+    pub fn remove_staging_dir(cyberpunk_dir: &Path, mod_name: &str) -> Result<()> {
+        let staging_dir = Self::staging_dir(cyberpunk_dir, mod_name);
+        if staging_dir.exists() {
+            fs::remove_dir_all(staging_dir)?;
+        }
+        Ok(())
+    }
+
+    pub fn load(cyberpunk_dir: &Path) -> Result<Option<Self>> {
+        let journal_path = Self::journal_path(cyberpunk_dir);
+        if !journal_path.exists() {
+            return Ok(None);
+        }
+        let journal_json = fs::read_to_string(journal_path)?;
+        Ok(Some(serde_json::from_str(&journal_json)?))
+    }
+
+    pub fn save(&self, cyberpunk_dir: &Path) -> Result<()> {
+        fs::create_dir_all(InstallManifest::data_dir(cyberpunk_dir))?;
+        // a torn journal could be neither completed nor reverted
+        write_file_atomically(&Self::journal_path(cyberpunk_dir), serde_json::to_string_pretty(self)?)
+    }
+
+    /// Persist the journal and apply it, rolling everything back if any step fails
+    pub fn run(&self, cyberpunk_dir: &Path) -> Result<()> {
+        self.run_cancellable(cyberpunk_dir, &ProgressHandle::default())
+    }
+
+    /// Like `run`, cancelling the operation between two steps rolls everything back as well
+    pub fn run_cancellable(&self, cyberpunk_dir: &Path, progress: &ProgressHandle) -> Result<()> {
+        self.save(cyberpunk_dir)?;
+        if let Err(err) = self.apply(cyberpunk_dir, progress) {
+            self.revert(cyberpunk_dir)?;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Apply every step that has not been applied yet and commit the manifest change
+    pub fn complete(&self, cyberpunk_dir: &Path) -> Result<()> {
+        self.apply(cyberpunk_dir, &ProgressHandle::default())
+    }
+
+    fn apply(&self, cyberpunk_dir: &Path, progress: &ProgressHandle) -> Result<()> {
+        for file_move in &self.moves {
+            progress.check_cancelled()?;
+            file_move.apply()?;
+        }
+        let mut manifest = InstallManifest::load(cyberpunk_dir)?;
+        match &self.manifest_change {
+            ManifestChange::Insert(installed_mod) => manifest.insert(installed_mod.clone()),
+            ManifestChange::Remove(mod_name) => {
+                manifest.remove(mod_name);
+            }
+            ManifestChange::Reassign(mod_name, successors) => {
+                manifest.remove(mod_name);
+                for successor in successors {
+                    manifest.insert(successor.clone());
+                }
+            }
+        }
+        manifest.save(cyberpunk_dir)?;
+        if self.kind == JournalKind::Uninstall {
+            BackupStore::for_mod(cyberpunk_dir, &self.mod_name).clear()?;
+            remove_empty_dirs(cyberpunk_dir, &self.created_dirs)?;
+        }
+        self.finish(cyberpunk_dir)
+    }
+
+    /// Undo every step that has been applied, leaving the manifest untouched
+    pub fn revert(&self, cyberpunk_dir: &Path) -> Result<()> {
+        for file_move in self.moves.iter().rev() {
+            file_move.undo()?;
+        }
+        if self.kind == JournalKind::Install {
+            BackupStore::for_mod(cyberpunk_dir, &self.mod_name).clear()?;
+        }
+        if self.kind != JournalKind::Uninstall {
+            remove_empty_dirs(cyberpunk_dir, &self.created_dirs)?;
+        }
+        self.finish(cyberpunk_dir)
+    }
+
+    fn finish(&self, cyberpunk_dir: &Path) -> Result<()> {
+        Self::remove_staging_dir(cyberpunk_dir, &self.mod_name)?;
+        fs::remove_file(Self::journal_path(cyberpunk_dir))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use super::*;
+
+    fn game_dir(name: &str) -> PathBuf {
+        let dir = temp_dir().join("cyberpunk_mod_manager_tests").join(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(dir.join("archive")).unwrap();
+        dir
+    }
+
+    fn install_journal(cyberpunk_dir: &Path) -> Journal {
+        let staged = Journal::staging_dir(cyberpunk_dir, "mod.zip").join("archive").join("mod.archive");
+        fs::create_dir_all(staged.parent().unwrap()).unwrap();
+        fs::write(&staged, "mod").unwrap();
+        let installed_mod = InstalledMod::new("mod.zip".to_string(), PathBuf::from("mod.zip"), String::new());
+        let mut journal = Journal::new(JournalKind::Install, "mod.zip".to_string(), ManifestChange::Insert(installed_mod));
+        journal.moves.push(FileMove::new(staged, cyberpunk_dir.join("archive").join("mod.archive")));
+        journal
+    }
+
+    #[test]
+    fn should_complete_interrupted_install() {
+        let cyberpunk_dir = game_dir("complete");
+        let journal = install_journal(&cyberpunk_dir);
+        journal.save(&cyberpunk_dir).unwrap();
+
+        let journal = Journal::load(&cyberpunk_dir).unwrap().unwrap();
+        journal.complete(&cyberpunk_dir).unwrap();
+
+        assert!(cyberpunk_dir.join("archive").join("mod.archive").is_file());
+        assert!(InstallManifest::load(&cyberpunk_dir).unwrap().get("mod.zip").is_some());
+        assert!(Journal::load(&cyberpunk_dir).unwrap().is_none());
+    }
+
+    #[test]
+    fn should_revert_partially_applied_install() {
+        let cyberpunk_dir = game_dir("revert");
+        fs::write(cyberpunk_dir.join("archive").join("original.archive"), "original").unwrap();
+        let mut journal = install_journal(&cyberpunk_dir);
+        let backup = BackupStore::for_mod(&cyberpunk_dir, "mod.zip").backup_path(Path::new("archive/original.archive"));
+        journal.moves.insert(0, FileMove::new(cyberpunk_dir.join("archive").join("original.archive"), backup));
+        journal.save(&cyberpunk_dir).unwrap();
+        // only the backup step went through before the failure
+        journal.moves[0].apply().unwrap();
+
+        journal.revert(&cyberpunk_dir).unwrap();
+
+        assert_eq!(fs::read_to_string(cyberpunk_dir.join("archive").join("original.archive")).unwrap(), "original");
+        assert!(!cyberpunk_dir.join("archive").join("mod.archive").exists());
+        assert!(InstallManifest::load(&cyberpunk_dir).unwrap().get("mod.zip").is_none());
+        assert!(Journal::load(&cyberpunk_dir).unwrap().is_none());
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::MODLIST_FILE_NAME;
+
+use super::{
+    installer::write_file_atomically,
+    paths::{mod_archive_dir, to_portable_string, CaseResolver},
+};
+
+/// Where newly installed archives are put in the load order
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadOrderPosition {
+    /// Loaded first, wins over every other archive
+    Top,
+    #[default]
+    Bottom,
+}
+
+impl LoadOrderPosition {
+    pub fn to_str(&self) -> &str {
+        match self {
+            LoadOrderPosition::Top => "top",
+            LoadOrderPosition::Bottom => "bottom",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            LoadOrderPosition::Top => LoadOrderPosition::Bottom,
+            LoadOrderPosition::Bottom => LoadOrderPosition::Top,
+        }
+    }
+}
+
+/// Where an archive of `archive/pc/mod` is, relative to the Cyberpunk folder and with its casing
+pub fn archive_path(cyberpunk_dir: &Path, archive: &str) -> PathBuf {
+    CaseResolver::new(cyberpunk_dir).resolve(&mod_archive_dir()).join(archive)
+}
+
+pub fn archive_dir(cyberpunk_dir: &Path) -> PathBuf {
+    cyberpunk_dir.join(CaseResolver::new(cyberpunk_dir).resolve(&mod_archive_dir()))
+}
+
+/// Whether a path relative to the Cyberpunk folder is an archive loaded from `archive/pc/mod`
+pub fn is_loose_archive(path: &Path) -> bool {
+    let parent = path.parent().map(to_portable_string).unwrap_or_default();
+    parent.to_lowercase() == to_portable_string(&mod_archive_dir()) && to_portable_string(path).to_lowercase().ends_with(".archive")
+}
+
+/// File names of the paths that are archives loaded from `archive/pc/mod`
+pub fn archive_names(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .filter(|path| is_loose_archive(path))
+        .filter_map(|path| path.file_name().map(|file_name| file_name.to_string_lossy().to_string()))
+        .collect()
+}
+
+pub fn modlist_path(cyberpunk_dir: &Path) -> PathBuf {
+    archive_dir(cyberpunk_dir).join(MODLIST_FILE_NAME)
+}
+
+/// The `.archive` files in `archive/pc/mod`, sorted the way the game loads unlisted archives
+pub fn present_archives(cyberpunk_dir: &Path) -> Result<Vec<String>> {
+    let archive_dir = archive_dir(cyberpunk_dir);
+    if !archive_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut archives: Vec<String> = fs::read_dir(archive_dir)?
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|file_name| file_name.to_lowercase().ends_with(".archive"))
+        .collect();
+    archives.sort_by_key(|file_name| file_name.to_lowercase());
+    Ok(archives)
+}
+
+pub fn read_modlist(cyberpunk_dir: &Path) -> Result<Vec<String>> {
+    let modlist_path = modlist_path(cyberpunk_dir);
+    if !modlist_path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(fs::read_to_string(modlist_path)?
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+pub fn write_modlist(cyberpunk_dir: &Path, load_order: &[String]) -> Result<()> {
+    fs::create_dir_all(archive_dir(cyberpunk_dir))?;
+    let mut modlist = load_order.join("\n");
+    modlist.push('\n');
+    write_file_atomically(&modlist_path(cyberpunk_dir), modlist)
+}
+
+/// The order the game loads archives in: the ones listed in `modlist.txt` first, then the
+/// rest alphabetically. Entries of archives that no longer exist are dropped
+pub fn merge_load_order(listed: &[String], present: &[String]) -> Vec<String> {
+    let mut load_order: Vec<String> = listed
+        .iter()
+        .filter_map(|entry| present.iter().find(|archive| archive.eq_ignore_ascii_case(entry)))
+        .cloned()
+        .collect();
+    load_order.dedup();
+    for archive in present {
+        if !load_order.contains(archive) {
+            load_order.push(archive.clone());
+        }
+    }
+    load_order
+}
+
+/// Move `new_archives` to `position`, keeping their order among themselves
+pub fn place_archives(load_order: &mut Vec<String>, new_archives: &[String], position: LoadOrderPosition) {
+    let mut placed: Vec<String> = load_order
+        .iter()
+        .filter(|archive| new_archives.iter().any(|new_archive| new_archive.eq_ignore_ascii_case(archive)))
+        .cloned()
+        .collect();
+    load_order.retain(|archive| !placed.contains(archive));
+    match position {
+        LoadOrderPosition::Top => {
+            placed.append(load_order);
+            *load_order = placed;
+        }
+        LoadOrderPosition::Bottom => load_order.append(&mut placed),
+    }
+}
+
+pub fn current_load_order(cyberpunk_dir: &Path) -> Result<Vec<String>> {
+    Ok(merge_load_order(&read_modlist(cyberpunk_dir)?, &present_archives(cyberpunk_dir)?))
+}
+
+/// Bring `modlist.txt` up to date after archives were added or removed, placing the added ones
+pub fn sync_modlist(cyberpunk_dir: &Path, new_archives: &[String], position: LoadOrderPosition) -> Result<()> {
+    if new_archives.is_empty() && !modlist_path(cyberpunk_dir).exists() {
+        return Ok(());
+    }
+    let mut load_order = current_load_order(cyberpunk_dir)?;
+    if load_order.is_empty() {
+        // nothing left to order, the folder is left the way it was before any archive
+        fs::remove_file(modlist_path(cyberpunk_dir))?;
+        return Ok(());
+    }
+    place_archives(&mut load_order, new_archives, position);
+    write_modlist(cyberpunk_dir, &load_order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn should_list_modlist_entries_first() {
+        let listed = names(&["Zeta.archive", "gone.archive", "alpha.archive"]);
+        let present = names(&["alpha.archive", "beta.archive", "zeta.archive"]);
+        assert_eq!(merge_load_order(&listed, &present), names(&["zeta.archive", "alpha.archive", "beta.archive"]));
+    }
+
+    #[test]
+    fn should_find_loose_archives() {
+        let paths: Vec<PathBuf> = ["Archive/PC/Mod/a.archive", "archive/pc/mod/a.archive.xl", "mods/b/archives/b.archive"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(archive_names(&paths), names(&["a.archive"]));
+    }
+
+    #[test]
+    fn should_place_new_archives() {
+        let mut load_order = names(&["a.archive", "new.archive", "b.archive"]);
+        place_archives(&mut load_order, &names(&["new.archive"]), LoadOrderPosition::Top);
+        assert_eq!(load_order, names(&["new.archive", "a.archive", "b.archive"]));
+        place_archives(&mut load_order, &names(&["new.archive"]), LoadOrderPosition::Bottom);
+        assert_eq!(load_order, names(&["a.archive", "b.archive", "new.archive"]));
+    }
+}
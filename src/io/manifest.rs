@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::constants::{
+    DISABLED_ARCHIVE_EXTENSION, DISABLED_MODS_DIR_NAME, DISABLED_REDMODS_DIR_NAME, GAME_DATA_DIR_NAME,
+    INSTALL_MANIFEST_FILE_NAME,
+};
+
+use super::{
+    fomod::FomodChoices,
+    installer::write_file_atomically,
+    load_order::is_loose_archive,
+    paths::{portable_path, portable_paths},
+};
+
+/// A single file the manager wrote into the Cyberpunk folder
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstalledFile {
+    /// Path relative to the Cyberpunk folder
+    #[serde(with = "portable_path")]
+    pub path: PathBuf,
+    pub size: u64,
+    pub hash: String,
+    /// The file replaced a game file which is kept in the mod's backup store
+    #[serde(default)]
+    pub backed_up: bool,
+    /// The installed mod that owned the file before it was overwritten
+    #[serde(default)]
+    pub previous_owner: Option<String>,
+    /// The file was moved out of the way when the mod was disabled
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// Everything we know about one installed mod
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstalledMod {
+    pub mod_name: String,
+    pub archive_path: PathBuf,
+    pub archive_hash: String,
+    /// Seconds since the unix epoch
+    pub installed_at: u64,
+    pub files: Vec<InstalledFile>,
+    /// Folder inside the archive that was installed, empty when the archive mirrors the Cyberpunk folder
+    #[serde(default, with = "portable_path")]
+    pub archive_root: PathBuf,
+    /// Options picked in the FOMOD installer, repairs install the same files again
+    #[serde(default)]
+    pub fomod_choices: Option<FomodChoices>,
+    /// Directories that did not exist before the install, relative to the Cyberpunk folder
+    #[serde(default, with = "portable_paths")]
+    pub created_dirs: Vec<PathBuf>,
+    /// The mod is installed but its files are kept out of the game
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+impl InstalledMod {
+    pub fn new(mod_name: String, archive_path: PathBuf, archive_hash: String) -> Self {
+        Self {
+            mod_name,
+            archive_path,
+            archive_hash,
+            installed_at: unix_timestamp(),
+            files: vec![],
+            archive_root: PathBuf::new(),
+            fomod_choices: None,
+            created_dirs: vec![],
+            disabled: false,
+        }
+    }
+}
+
+impl InstalledFile {
+    /// Whether the file at `location` is still the one that was installed
+    pub fn is_unchanged(&self, location: &Path) -> Result<bool> {
+        Ok(fs::metadata(location)?.len() == self.size && hash_file(location)? == self.hash)
+    }
+}
+
+/// The install database kept inside every Cyberpunk folder the manager touches,
+/// keyed by the file name of the mod archive
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct InstallManifest {
+    pub mods: BTreeMap<String, InstalledMod>,
+    /// Folders of `mods/` in the order REDmod deploys them
+    #[serde(default)]
+    pub redmod_order: Vec<String>,
+    /// REDmod folders moved out of `mods/` into the data folder
+    #[serde(default)]
+    pub disabled_redmods: Vec<String>,
+}
+
+impl InstallManifest {
+    pub fn data_dir(cyberpunk_dir: &Path) -> PathBuf {
+        cyberpunk_dir.join(GAME_DATA_DIR_NAME)
+    }
+
+    pub fn manifest_path(cyberpunk_dir: &Path) -> PathBuf {
+        Self::data_dir(cyberpunk_dir).join(INSTALL_MANIFEST_FILE_NAME)
+    }
+
+    pub fn disabled_redmods_dir(cyberpunk_dir: &Path) -> PathBuf {
+        Self::data_dir(cyberpunk_dir).join(DISABLED_REDMODS_DIR_NAME)
+    }
+
+    /// Where the files of a disabled mod are parked
+    pub fn disabled_mod_dir(cyberpunk_dir: &Path, mod_name: &str) -> PathBuf {
+        Self::data_dir(cyberpunk_dir).join(DISABLED_MODS_DIR_NAME).join(mod_name)
+    }
+
+    /// Where a file goes when its mod is disabled. `.archive` files are renamed where they are so
+    /// the game skips them, everything else is moved to the mod's parking folder
+    pub fn disabled_location(cyberpunk_dir: &Path, mod_name: &str, path: &Path) -> PathBuf {
+        if is_loose_archive(path) {
+            let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+            file_name.push(format!(".{}", DISABLED_ARCHIVE_EXTENSION));
+            cyberpunk_dir.join(path.with_file_name(file_name))
+        } else {
+            Self::disabled_mod_dir(cyberpunk_dir, mod_name).join(path)
+        }
+    }
+
+    /// Where a recorded file of a mod is right now. Files of disabled mods and of disabled
+    /// REDmods are parked outside of the game folders
+    pub fn location_of(&self, cyberpunk_dir: &Path, mod_name: &str, installed_file: &InstalledFile) -> PathBuf {
+        let path = &installed_file.path;
+        if installed_file.disabled {
+            return Self::disabled_location(cyberpunk_dir, mod_name, path);
+        }
+        let mut components = path.iter();
+        if let (Some(mods), Some(folder)) = (components.next(), components.next()) {
+            let is_disabled = self.disabled_redmods.iter().any(|disabled| disabled.as_str() == folder);
+            if mods.eq_ignore_ascii_case("mods") && is_disabled {
+                return Self::disabled_redmods_dir(cyberpunk_dir).join(folder).join(components.as_path());
+            }
+        }
+        cyberpunk_dir.join(path)
+    }
+
+    /// Load the manifest of a Cyberpunk folder, an empty one is returned if nothing was installed yet
+    pub fn load(cyberpunk_dir: &Path) -> Result<Self> {
+        let manifest_path = Self::manifest_path(cyberpunk_dir);
+        if !manifest_path.exists() {
+            return Ok(Self::default());
+        }
+        let manifest_json = fs::read_to_string(manifest_path)?;
+        Ok(serde_json::from_str(&manifest_json)?)
+    }
+
+    pub fn save(&self, cyberpunk_dir: &Path) -> Result<()> {
+        fs::create_dir_all(Self::data_dir(cyberpunk_dir))?;
+        write_file_atomically(&Self::manifest_path(cyberpunk_dir), serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn get(&self, mod_name: &str) -> Option<&InstalledMod> {
+        self.mods.get(mod_name)
+    }
+
+    /// The mod and every mod with a file at one of its paths, what taking it out of the game is worked out from
+    pub fn sharing_files_with(&self, mod_name: &str) -> Vec<&InstalledMod> {
+        let Some(installed_mod) = self.get(mod_name) else {
+            return vec![];
+        };
+        self.mods
+            .values()
+            .filter(|other| {
+                other.mod_name == mod_name
+                    || other.files.iter().any(|other_file| {
+                        installed_mod.files.iter().any(|installed_file| installed_file.path == other_file.path)
+                    })
+            })
+            .collect()
+    }
+
+    pub fn insert(&mut self, installed_mod: InstalledMod) {
+        self.mods.insert(installed_mod.mod_name.clone(), installed_mod);
+    }
+
+    pub fn remove(&mut self, mod_name: &str) -> Option<InstalledMod> {
+        self.mods.remove(mod_name)
+    }
+
+    /// Who owns every recorded path, build it once per operation and look files up in it
+    pub fn owners(&self) -> OwnerIndex {
+        let mut index = OwnerIndex::default();
+        // the mods with a file in the game at each path, in manifest order, and who they overwrote
+        let mut writers: HashMap<&Path, Vec<(&str, Option<&str>)>> = HashMap::new();
+        for installed_mod in self.mods.values() {
+            for installed_file in &installed_mod.files {
+                let previous_owner = installed_file.previous_owner.as_deref();
+                if let Some(previous_owner) = previous_owner {
+                    index
+                        .successors
+                        .entry((installed_file.path.clone(), previous_owner.to_string()))
+                        .or_insert_with(|| installed_mod.mod_name.clone());
+                }
+                if !installed_file.disabled {
+                    writers
+                        .entry(installed_file.path.as_path())
+                        .or_default()
+                        .push((installed_mod.mod_name.as_str(), previous_owner));
+                }
+            }
+        }
+        for (path, path_writers) in writers {
+            let owner = path_writers
+                .iter()
+                .find(|(mod_name, _)| !path_writers.iter().any(|(_, previous_owner)| *previous_owner == Some(*mod_name)));
+            if let Some((mod_name, _)) = owner {
+                index.owners.insert(path.to_path_buf(), mod_name.to_string());
+            }
+        }
+        index
+    }
+}
+
+/// The owner of every path of the manifest. When several mods list the same path, the owner is
+/// the one no other mod names as the previous owner. Disabled files own nothing
+#[derive(Debug, Clone, Default)]
+pub struct OwnerIndex {
+    owners: HashMap<PathBuf, String>,
+    /// (path, mod that wrote it before) -> mod that overwrote it
+    successors: HashMap<(PathBuf, String), String>,
+}
+
+impl OwnerIndex {
+    /// The mod that most recently wrote `path`
+    pub fn owner_of(&self, path: &Path) -> Option<&str> {
+        self.owners.get(path).map(|owner| owner.as_str())
+    }
+
+    /// The mod that overwrote `path` after `mod_name` wrote it, it takes over the file when
+    /// `mod_name` is uninstalled
+    pub fn successor_of(&self, mod_name: &str, path: &Path) -> Option<&str> {
+        self.successors
+            .get(&(path.to_path_buf(), mod_name.to_string()))
+            .map(|successor| successor.as_str())
+    }
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Hex encoded sha256 of a file's content
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed_mod(mod_name: &str, files: &[(&str, Option<&str>, bool)]) -> InstalledMod {
+        let mut installed_mod = InstalledMod::new(mod_name.to_string(), PathBuf::from(mod_name), String::new());
+        for (path, previous_owner, disabled) in files {
+            installed_mod.files.push(InstalledFile {
+                path: PathBuf::from(path),
+                size: 0,
+                hash: String::new(),
+                backed_up: false,
+                previous_owner: previous_owner.map(|owner| owner.to_string()),
+                disabled: *disabled,
+            });
+        }
+        installed_mod
+    }
+
+    #[test]
+    fn should_index_owners_along_overwrite_chains() {
+        let mut manifest = InstallManifest::default();
+        manifest.insert(installed_mod("a.zip", &[("shared.txt", None, false), ("only_a.txt", None, false)]));
+        manifest.insert(installed_mod("b.zip", &[("shared.txt", Some("a.zip"), false)]));
+        // a disabled mod owns nothing, even what it overwrote
+        manifest.insert(installed_mod("c.zip", &[("shared.txt", Some("b.zip"), true)]));
+
+        let owners = manifest.owners();
+        assert_eq!(owners.owner_of(Path::new("shared.txt")), Some("b.zip"));
+        assert_eq!(owners.owner_of(Path::new("only_a.txt")), Some("a.zip"));
+        assert_eq!(owners.owner_of(Path::new("other.txt")), None);
+        assert_eq!(owners.successor_of("a.zip", Path::new("shared.txt")), Some("b.zip"));
+        assert_eq!(owners.successor_of("b.zip", Path::new("shared.txt")), Some("c.zip"));
+        assert_eq!(owners.successor_of("c.zip", Path::new("shared.txt")), None);
+    }
+}
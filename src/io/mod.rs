@@ -1,12 +1,106 @@
+use std::path::PathBuf;
+
+use self::{batch::BatchOperation, fomod::FomodChoices};
+
+pub mod backup;
+pub mod batch;
+pub mod conflicts;
+pub mod extraction_cache;
+pub mod fomod;
+pub mod frameworks;
 pub mod handler;
+pub mod installer;
+pub mod jobs;
+pub mod journal;
+pub mod load_order;
+pub mod manifest;
+pub mod mod_type;
+pub mod paths;
+pub mod plan;
+pub mod profiles;
+pub mod progress;
+pub mod redmod;
+pub mod status;
 
 #[derive(Debug, Clone)]
 pub enum IoEvent {
     Initialize,      // Launch to initialize the application
-    InstallMod,      // Install a mod
+    InstallMod,      // Install a mod, asking what to do if it conflicts with other mods
+    InstallModOverwritingConflicts, // Install a mod replacing files owned by other mods
+    InstallModSkippingConflicts,    // Install a mod without the files owned by other mods
+    SelectArchiveRoot(PathBuf),     // Install a mod from the chosen folder of an archive with several game roots
+    SelectFomodChoices(FomodChoices), // Install a mod with the options picked in its FOMOD installer
+    InstallModIgnoringFrameworks,   // Install a mod even though frameworks it needs are missing
+    InstallFramework(String),       // Install a framework archive from the mod folder, then the mod that needs it
     UninstallMod,    // Uninstall a mod
+    RepairMod,       // Restore missing or modified files of an installed mod
+    DisableMod,      // Move the files of an installed mod out of the game
+    EnableMod,       // Put the files of a disabled mod back
     CheckIfModIsInstalled, // Check if a mod is installed
+    DetectFrameworks, // Look up which frameworks are installed and their versions
+    LoadLoadOrder,   // Read the order archives are loaded in
+    SaveLoadOrder(Vec<String>), // Write a new archive load order to modlist.txt
+    LoadRedmods,     // Read the REDmods of the cyberpunk folder with their info.json, recording new and removed ones
+    ToggleRedmod(String), // Move a REDmod folder in or out of mods/
+    SaveRedmodOrder(Vec<String>), // Record a new REDmod deploy order in the manifest
+    CompleteJournal, // Finish an interrupted install or uninstall
+    RevertJournal,   // Roll back an interrupted install or uninstall
+    SaveProfile(String),   // Record the installed mods as a profile and save it with the settings
+    SwitchProfile(String), // Show what switching to a profile would change
+    ApplyProfile(String),  // Install, uninstall, enable and disable mods until the folder matches a profile
+    RunBatch(BatchOperation, Vec<String>), // Run one operation on every marked mod, frameworks first, or show what it changes first
+    ApplyBatch(BatchOperation, Vec<String>), // Run a batch whose changes were shown and confirmed
     SaveSettings,    // Save settings
     LoadMods,        // Load mods into app
     DeleteTempDir,   // Delete the temp dir on exit
 }
+
+impl IoEvent {
+    /// Events that write to the game folder or the settings run one at a time, in the order they
+    /// were dispatched. The others only read and run next to them. Loading the mods resets the file
+    /// list and looks for an unfinished journal, which a running install still has, so it waits too
+    pub fn is_serialized(&self) -> bool {
+        !matches!(self, IoEvent::CheckIfModIsInstalled | IoEvent::DetectFrameworks | IoEvent::LoadLoadOrder)
+    }
+
+    /// Short description for the job list
+    pub fn description(&self) -> String {
+        match self {
+            IoEvent::Initialize => "Initialize".to_string(),
+            IoEvent::InstallMod => "Install mod".to_string(),
+            IoEvent::InstallModOverwritingConflicts => "Install mod, overwriting conflicts".to_string(),
+            IoEvent::InstallModSkippingConflicts => "Install mod, skipping conflicts".to_string(),
+            IoEvent::SelectArchiveRoot(archive_root) => {
+                format!("Install mod from {}", archive_root.to_string_lossy())
+            }
+            IoEvent::SelectFomodChoices(_) => "Install mod with FOMOD choices".to_string(),
+            IoEvent::InstallModIgnoringFrameworks => "Install mod without its frameworks".to_string(),
+            IoEvent::InstallFramework(framework_archive) => format!("Install framework {}", framework_archive),
+            IoEvent::UninstallMod => "Uninstall mod".to_string(),
+            IoEvent::RepairMod => "Repair mod".to_string(),
+            IoEvent::DisableMod => "Disable mod".to_string(),
+            IoEvent::EnableMod => "Enable mod".to_string(),
+            IoEvent::CheckIfModIsInstalled => "Check mod status".to_string(),
+            IoEvent::DetectFrameworks => "Detect frameworks".to_string(),
+            IoEvent::LoadLoadOrder => "Load the load order".to_string(),
+            IoEvent::SaveLoadOrder(_) => "Save the load order".to_string(),
+            IoEvent::LoadRedmods => "Load REDmods".to_string(),
+            IoEvent::ToggleRedmod(folder) => format!("Toggle REDmod {}", folder),
+            IoEvent::SaveRedmodOrder(_) => "Save the REDmod order".to_string(),
+            IoEvent::CompleteJournal => "Complete the unfinished operation".to_string(),
+            IoEvent::RevertJournal => "Revert the unfinished operation".to_string(),
+            IoEvent::SaveProfile(name) => format!("Save profile {}", name),
+            IoEvent::SwitchProfile(name) => format!("Plan the switch to profile {}", name),
+            IoEvent::ApplyProfile(name) => format!("Switch to profile {}", name),
+            IoEvent::RunBatch(operation, mod_names) if operation.is_planned() => {
+                format!("Plan to {} {} mods", operation.to_str().to_lowercase(), mod_names.len())
+            }
+            IoEvent::RunBatch(operation, mod_names) | IoEvent::ApplyBatch(operation, mod_names) => {
+                format!("{} {} mods", operation.to_str(), mod_names.len())
+            }
+            IoEvent::SaveSettings => "Save settings".to_string(),
+            IoEvent::LoadMods => "Load mods".to_string(),
+            IoEvent::DeleteTempDir => "Delete the temp dir".to_string(),
+        }
+    }
+}
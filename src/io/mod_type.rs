@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use log::debug;
+
+use super::{
+    installer::{list_mod_files, looks_like_mod},
+    paths::to_portable_string,
+};
+
+/// The modding frameworks other mods build on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Framework {
+    Cet,
+    Red4ext,
+    Redscript,
+    ArchiveXl,
+    TweakXl,
+    Codeware,
+}
+
+impl Framework {
+    pub fn all() -> Vec<Framework> {
+        vec![
+            Framework::Cet,
+            Framework::Red4ext,
+            Framework::Redscript,
+            Framework::ArchiveXl,
+            Framework::TweakXl,
+            Framework::Codeware,
+        ]
+    }
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            Framework::Cet => "Cyber Engine Tweaks",
+            Framework::Red4ext => "RED4ext",
+            Framework::Redscript => "redscript",
+            Framework::ArchiveXl => "ArchiveXL",
+            Framework::TweakXl => "TweakXL",
+            Framework::Codeware => "Codeware",
+        }
+    }
+
+    /// A file only the framework itself ships, relative to the Cyberpunk folder in portable lowercase form
+    pub fn signature(&self) -> &str {
+        match self {
+            Framework::Cet => "bin/x64/plugins/cyber_engine_tweaks.asi",
+            Framework::Red4ext => "red4ext/red4ext.dll",
+            Framework::Redscript => "engine/tools/scc.exe",
+            Framework::ArchiveXl => "red4ext/plugins/archivexl/archivexl.dll",
+            Framework::TweakXl => "red4ext/plugins/tweakxl/tweakxl.dll",
+            Framework::Codeware => "red4ext/plugins/codeware/codeware.dll",
+        }
+    }
+}
+
+/// What kind of content a mod ships, a mod can be several kinds at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModType {
+    Framework,
+    Archive,
+    ArchiveXl,
+    Cet,
+    Redscript,
+    TweakXl,
+    Red4ext,
+    Redmod,
+    Other,
+}
+
+impl ModType {
+    pub fn all() -> Vec<ModType> {
+        vec![
+            ModType::Framework,
+            ModType::Archive,
+            ModType::ArchiveXl,
+            ModType::Cet,
+            ModType::Redscript,
+            ModType::TweakXl,
+            ModType::Red4ext,
+            ModType::Redmod,
+            ModType::Other,
+        ]
+    }
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            ModType::Framework => "Framework",
+            ModType::Archive => "Archive",
+            ModType::ArchiveXl => "ArchiveXL",
+            ModType::Cet => "CET",
+            ModType::Redscript => "Redscript",
+            ModType::TweakXl => "TweakXL",
+            ModType::Red4ext => "RED4ext",
+            ModType::Redmod => "REDmod",
+            ModType::Other => "Other",
+        }
+    }
+}
+
+/// The types of a mod together with the frameworks it is, if it is one
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ModClassification {
+    pub types: Vec<ModType>,
+    pub frameworks: Vec<Framework>,
+    /// Whether the archive has anything the game loads, see `looks_like_mod`
+    pub looks_like_mod: bool,
+}
+
+impl ModClassification {
+    pub fn has_type(&self, mod_type: ModType) -> bool {
+        self.types.contains(&mod_type)
+    }
+
+    /// Short label for the file list and the mod popup
+    pub fn label(&self) -> String {
+        if !self.frameworks.is_empty() {
+            let frameworks: Vec<&str> = self.frameworks.iter().map(|framework| framework.to_str()).collect();
+            return format!("{}: {}", ModType::Framework.to_str(), frameworks.join(", "));
+        }
+        self.types.iter().map(|mod_type| mod_type.to_str()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+fn is_redmod_info(path: &str) -> bool {
+    let components: Vec<&str> = path.split('/').collect();
+    components.len() >= 3
+        && components[components.len() - 1] == "info.json"
+        && components[components.len() - 3] == "mods"
+}
+
+/// Classify a mod from the paths inside its archive. Wrapper folders and FOMOD option folders
+/// are fine, the known game folders are looked for anywhere in a path
+pub fn classify_mod(paths: &[PathBuf]) -> ModClassification {
+    let is_mod = looks_like_mod(paths);
+    // a leading `/` lets folder checks match at the start of a path too
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| format!("/{}", to_portable_string(path).to_lowercase()))
+        .collect();
+    let frameworks: Vec<Framework> = Framework::all()
+        .into_iter()
+        .filter(|framework| {
+            let signature = format!("/{}", framework.signature());
+            paths.iter().any(|path| path.ends_with(&signature))
+        })
+        .collect();
+    if !frameworks.is_empty() {
+        return ModClassification {
+            types: vec![ModType::Framework],
+            frameworks,
+            looks_like_mod: is_mod,
+        };
+    }
+    let mut types = vec![];
+    for path in &paths {
+        let extension = Path::new(path).extension().unwrap_or_default().to_string_lossy().to_string();
+        let mod_type = if extension == "xl" {
+            ModType::ArchiveXl
+        } else if extension == "archive" && !path.contains("/mods/") {
+            ModType::Archive
+        } else if path.contains("/bin/x64/plugins/cyber_engine_tweaks/mods/") {
+            ModType::Cet
+        } else if extension == "reds" {
+            ModType::Redscript
+        } else if path.contains("/r6/tweaks/") {
+            ModType::TweakXl
+        } else if path.contains("/red4ext/plugins/") {
+            ModType::Red4ext
+        } else if is_redmod_info(path) {
+            ModType::Redmod
+        } else {
+            continue;
+        };
+        if !types.contains(&mod_type) {
+            types.push(mod_type);
+        }
+    }
+    // keep the order of `ModType::all` so labels read the same for every mod
+    types.sort_by_key(|mod_type| ModType::all().iter().position(|other| other == mod_type));
+    if types.is_empty() {
+        types.push(ModType::Other);
+    }
+    ModClassification {
+        types,
+        frameworks: vec![],
+        looks_like_mod: is_mod,
+    }
+}
+
+/// Classify every archive of the mod folder from its index, reading several archives at once.
+/// Files that are no archives are left unclassified
+pub fn classify_archives(mod_folder: &Path, file_names: &[String]) -> HashMap<String, ModClassification> {
+    let workers = thread::available_parallelism().map(|workers| workers.get()).unwrap_or(1);
+    let chunk_size = file_names.len().div_ceil(workers).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = file_names
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|file_name| match list_mod_files(&mod_folder.join(file_name)) {
+                            Ok(paths) => Some((file_name.clone(), classify_mod(&paths))),
+                            Err(err) => {
+                                debug!("Could not read {}: {:?}", file_name, err);
+                                None
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap_or_default()).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::paths::normalize_archive_path;
+
+    fn classify(paths: &[&str]) -> ModClassification {
+        let paths: Vec<PathBuf> = paths.iter().map(|path| normalize_archive_path(path).unwrap()).collect();
+        classify_mod(&paths)
+    }
+
+    #[test]
+    fn should_classify_mod_content() {
+        let classification = classify(&[
+            "MyMod/archive/pc/mod/my_mod.archive",
+            "MyMod/archive/pc/mod/my_mod.archive.xl",
+            "MyMod/bin/x64/plugins/cyber_engine_tweaks/mods/my_mod/init.lua",
+            "MyMod/r6/scripts/my_mod/main.reds",
+            "MyMod/r6/tweaks/my_mod.yaml",
+        ]);
+        assert_eq!(
+            classification.types,
+            vec![ModType::Archive, ModType::ArchiveXl, ModType::Cet, ModType::Redscript, ModType::TweakXl]
+        );
+        assert_eq!(classification.label(), "Archive, ArchiveXL, CET, Redscript, TweakXL");
+        assert!(classification.looks_like_mod);
+    }
+
+    #[test]
+    fn should_classify_redmod_and_frameworks() {
+        let redmod = classify(&["mods/my_redmod/info.json", "mods/my_redmod/archives/my_redmod.archive"]);
+        assert_eq!(redmod.types, vec![ModType::Redmod]);
+
+        let framework = classify(&["red4ext/plugins/ArchiveXL/ArchiveXL.dll", "r6/scripts/ArchiveXL.reds"]);
+        assert_eq!(framework.frameworks, vec![Framework::ArchiveXl]);
+        assert!(framework.has_type(ModType::Framework));
+
+        assert_eq!(classify(&["readme.txt"]).types, vec![ModType::Other]);
+        assert!(!classify(&["readme.txt"]).looks_like_mod);
+    }
+}
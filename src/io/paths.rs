@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// `archive/pc/mod`, where loose `.archive` files are loaded from
+pub fn mod_archive_dir() -> PathBuf {
+    ["archive", "pc", "mod"].iter().collect()
+}
+
+/// Turn a path found inside a mod archive into a relative path for the current platform.
+/// Archives packed on Windows often use `\` as separator, which Linux would otherwise keep
+/// as part of the file name. Paths leaving the folder they are unpacked into through `..`,
+/// a root or a drive are refused
+pub fn normalize_archive_path(path: &str) -> Option<PathBuf> {
+    if path.starts_with(['/', '\\']) {
+        return None;
+    }
+    let mut normalized = PathBuf::new();
+    for component in path.split(['/', '\\']).filter(|component| !component.is_empty() && *component != ".") {
+        // `C:` is a plain name on Linux but a drive on Windows, colons further in are just part of a name
+        if component == ".." || (normalized.as_os_str().is_empty() && is_drive_prefix(component)) {
+            return None;
+        }
+        normalized.push(component);
+    }
+    normalized
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then_some(normalized)
+}
+
+fn is_drive_prefix(component: &str) -> bool {
+    let mut chars = component.chars();
+    matches!((chars.next(), chars.next()), (Some(drive), Some(':')) if drive.is_ascii_alphabetic())
+}
+
+/// Normalize a path that is already relative to an extraction or game root
+pub fn normalize_relative_path(path: &Path) -> Option<PathBuf> {
+    normalize_archive_path(&path.to_string_lossy())
+}
+
+/// The `/` separated form used when paths are persisted, so a manifest reads the same on every platform
+pub fn to_portable_string(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Maps archive paths onto the game folder ignoring case. Mods are authored on Windows where
+/// `Archive/PC/Mod` and `archive/pc/mod` are the same folder, on case sensitive filesystems
+/// the existing casing has to be reused or the game never sees the files
+pub struct CaseResolver<'a> {
+    root: &'a Path,
+    /// lowercase portable path -> resolved path, so every file of an archive agrees on one casing
+    resolved: HashMap<String, PathBuf>,
+}
+
+impl<'a> CaseResolver<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self {
+            root,
+            resolved: HashMap::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, relative_path: &Path) -> PathBuf {
+        let mut resolved_path = PathBuf::new();
+        for component in relative_path.components() {
+            let name = component.as_os_str().to_string_lossy().to_string();
+            let key = to_portable_string(&resolved_path.join(&name)).to_lowercase();
+            if let Some(known) = self.resolved.get(&key) {
+                resolved_path = known.clone();
+                continue;
+            }
+            let existing_name = fs::read_dir(self.root.join(&resolved_path))
+                .ok()
+                .and_then(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .map(|entry| entry.file_name().to_string_lossy().to_string())
+                        .find(|entry_name| entry_name.to_lowercase() == name.to_lowercase())
+                });
+            resolved_path.push(existing_name.unwrap_or(name));
+            self.resolved.insert(key, resolved_path.clone());
+        }
+        resolved_path
+    }
+}
+
+/// Serde helpers storing relative paths in their portable form
+pub mod portable_path {
+    use std::path::{Path, PathBuf};
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::{normalize_archive_path, to_portable_string};
+
+    pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_portable_string(path))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        normalize_archive_path(&path).ok_or_else(|| D::Error::custom(format!("{} is not a relative path", path)))
+    }
+}
+
+/// Same as `portable_path` for lists of paths
+pub mod portable_paths {
+    use std::path::PathBuf;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer, ser::SerializeSeq};
+
+    use super::{normalize_archive_path, to_portable_string};
+
+    pub fn serialize<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(paths.len()))?;
+        for path in paths {
+            seq.serialize_element(&to_portable_string(path))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PathBuf>, D::Error> {
+        let paths = Vec::<String>::deserialize(deserializer)?;
+        paths
+            .iter()
+            .map(|path| normalize_archive_path(path).ok_or_else(|| D::Error::custom(format!("{} is not a relative path", path))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_split_windows_separators() {
+        let path = normalize_archive_path("archive\\pc\\mod\\my_mod.archive").unwrap();
+        assert_eq!(path, ["archive", "pc", "mod", "my_mod.archive"].iter().collect::<PathBuf>());
+    }
+
+    #[test]
+    fn should_handle_mixed_and_redundant_separators() {
+        let path = normalize_archive_path("./r6\\scripts//my_mod/main.reds").unwrap();
+        assert_eq!(path, ["r6", "scripts", "my_mod", "main.reds"].iter().collect::<PathBuf>());
+    }
+
+    #[test]
+    fn should_refuse_paths_leaving_the_root() {
+        assert_eq!(normalize_archive_path("archive/../../bin/x64/evil.dll"), None);
+        assert_eq!(normalize_archive_path("..\\evil.dll"), None);
+        assert_eq!(normalize_archive_path("/etc/passwd"), None);
+        assert_eq!(normalize_archive_path("\\Windows\\evil.dll"), None);
+        assert_eq!(normalize_archive_path("C:\\Windows\\evil.dll"), None);
+        assert_eq!(normalize_archive_path("C:evil.dll"), None);
+        assert_eq!(normalize_archive_path("./d:/evil.dll"), None);
+        assert_eq!(normalize_archive_path(""), Some(PathBuf::new()));
+        assert_eq!(normalize_relative_path(Path::new("r6/..\\..\\evil.reds")), None);
+    }
+
+    #[test]
+    fn should_keep_colons_inside_names() {
+        let path = normalize_archive_path("r6/scripts/mod: the sequel/main.reds").unwrap();
+        assert_eq!(path, ["r6", "scripts", "mod: the sequel", "main.reds"].iter().collect::<PathBuf>());
+        assert!(normalize_archive_path("archive/C:/a.archive").is_some());
+    }
+
+    #[test]
+    fn should_refuse_recorded_paths_leaving_the_root() {
+        #[derive(serde::Deserialize)]
+        struct Recorded {
+            #[serde(with = "portable_path")]
+            path: PathBuf,
+        }
+        let recorded: Recorded = serde_json::from_str(r#"{"path": "r6\\scripts\\a.reds"}"#).unwrap();
+        assert_eq!(recorded.path, ["r6", "scripts", "a.reds"].iter().collect::<PathBuf>());
+        assert!(serde_json::from_str::<Recorded>(r#"{"path": "../../evil.dll"}"#).is_err());
+    }
+
+    #[test]
+    fn should_reuse_existing_casing() {
+        let root = std::env::temp_dir().join("cyberpunk_mod_manager_tests").join("case_resolver");
+        if root.exists() {
+            fs::remove_dir_all(&root).unwrap();
+        }
+        fs::create_dir_all(root.join("archive").join("pc").join("mod")).unwrap();
+        let mut resolver = CaseResolver::new(&root);
+
+        let first = resolver.resolve(&normalize_archive_path("Archive/PC/Mod/First.archive").unwrap());
+        let new_dir = resolver.resolve(&normalize_archive_path("R6/Scripts/a.reds").unwrap());
+        let same_new_dir = resolver.resolve(&normalize_archive_path("r6/scripts/b.reds").unwrap());
+
+        assert_eq!(first, normalize_archive_path("archive/pc/mod/First.archive").unwrap());
+        assert_eq!(new_dir, normalize_archive_path("R6/Scripts/a.reds").unwrap());
+        assert_eq!(same_new_dir, normalize_archive_path("R6/Scripts/b.reds").unwrap());
+    }
+
+    #[test]
+    fn should_round_trip_portable_strings() {
+        let path: PathBuf = ["bin", "x64", "plugins", "cyber_engine_tweaks"].iter().collect();
+        assert_eq!(to_portable_string(&path), "bin/x64/plugins/cyber_engine_tweaks");
+        assert_eq!(normalize_archive_path(&to_portable_string(&path)).unwrap(), path);
+    }
+}
@@ -0,0 +1,322 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    installer::missing_parent_dirs,
+    journal::Journal,
+    manifest::{InstalledMod, OwnerIndex},
+    paths::to_portable_string,
+    progress::format_bytes,
+    IoEvent,
+};
+
+/// A file an operation touches, relative to the Cyberpunk folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    /// The mod the file in the game belongs to now, `None` for files of the game or put there by hand
+    pub owner: Option<String>,
+}
+
+/// Everything an install, uninstall, repair or profile switch would change, worked out without touching the game
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OperationPlan {
+    pub title: String,
+    /// Mod operations a profile switch runs, in order
+    pub steps: Vec<String>,
+    pub create: Vec<PlannedFile>,
+    pub overwrite: Vec<PlannedFile>,
+    pub remove: Vec<PlannedFile>,
+    /// Originals a mod replaced that go back into the game
+    pub restore: Vec<PlannedFile>,
+    pub create_dirs: Vec<PathBuf>,
+    /// Files of mods that stay installed which a profile switch overwrites without asking
+    pub taken: Vec<PlannedFile>,
+    /// Files of the archive left out because another of its files only differs from them in case
+    pub skip: Vec<PlannedFile>,
+    /// Files changed since they were installed, an uninstall leaves them in the game
+    pub kept: Vec<PlannedFile>,
+    /// Files moved into the kept backups of the mod so nothing the user had is lost: originals of
+    /// kept files and changed files a repair replaces
+    pub set_aside: Vec<PlannedFile>,
+    /// Files of an uninstalled mod that other mods overwrote, they keep them and what they replaced
+    pub reassigned: Vec<PlannedFile>,
+    /// Files earlier steps write and the mod they write them for
+    written_by: Vec<(PathBuf, String)>,
+}
+
+impl OperationPlan {
+    pub fn new(title: String) -> Self {
+        Self {
+            title,
+            ..Default::default()
+        }
+    }
+
+    /// A file of a mod written into the game, over whatever is there now
+    pub fn write(&mut self, cyberpunk_dir: &Path, owners: &OwnerIndex, path: &Path, size: u64) {
+        let owner = owners.owner_of(path).map(|owner| owner.to_string());
+        let planned_file = PlannedFile {
+            path: path.to_path_buf(),
+            size,
+            owner,
+        };
+        if cyberpunk_dir.join(path).is_file() {
+            self.overwrite.push(planned_file);
+        } else {
+            self.create.push(planned_file);
+        }
+        for dir in missing_parent_dirs(cyberpunk_dir, path) {
+            if !self.create_dirs.contains(&dir) {
+                self.create_dirs.push(dir);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, owners: &OwnerIndex, path: &Path, size: u64) {
+        self.remove.push(PlannedFile {
+            path: path.to_path_buf(),
+            size,
+            owner: owners.owner_of(path).map(|owner| owner.to_string()),
+        });
+    }
+
+    pub fn restore(&mut self, path: &Path, size: u64) {
+        self.restore.push(PlannedFile {
+            path: path.to_path_buf(),
+            size,
+            owner: None,
+        });
+    }
+
+    pub fn skip(&mut self, path: &Path, size: u64) {
+        self.skip.push(PlannedFile {
+            path: path.to_path_buf(),
+            size,
+            owner: None,
+        });
+    }
+
+    pub fn keep(&mut self, path: &Path, size: u64) {
+        self.kept.push(PlannedFile {
+            path: path.to_path_buf(),
+            size,
+            owner: None,
+        });
+    }
+
+    pub fn set_aside(&mut self, path: &Path, size: u64) {
+        self.set_aside.push(PlannedFile {
+            path: path.to_path_buf(),
+            size,
+            owner: None,
+        });
+    }
+
+    pub fn reassign(&mut self, path: &Path, size: u64, owner: &str) {
+        self.reassigned.push(PlannedFile {
+            path: path.to_path_buf(),
+            size,
+            owner: Some(owner.to_string()),
+        });
+    }
+
+    /// Whether `other` writes files an earlier step of this plan writes
+    pub fn writes_over_steps(&self, other: &OperationPlan) -> bool {
+        other
+            .create
+            .iter()
+            .chain(&other.overwrite)
+            .any(|planned_file| self.written_by.iter().any(|(path, _)| *path == planned_file.path))
+    }
+
+    /// Add the plan of one step of a larger operation, files an earlier step writes are overwritten
+    /// from the mod of that step
+    pub fn extend(&mut self, step: String, mod_name: &str, other: OperationPlan) {
+        self.steps.push(step);
+        let created = other.create.into_iter().map(|planned_file| (planned_file, true));
+        let overwritten = other.overwrite.into_iter().map(|planned_file| (planned_file, false));
+        for (mut planned_file, created) in created.chain(overwritten) {
+            match self.written_by.iter_mut().find(|(path, _)| *path == planned_file.path) {
+                Some((_, owner)) => {
+                    planned_file.owner = Some(std::mem::replace(owner, mod_name.to_string()));
+                    self.overwrite.push(planned_file);
+                }
+                None => {
+                    self.written_by.push((planned_file.path.clone(), mod_name.to_string()));
+                    if created {
+                        self.create.push(planned_file);
+                    } else {
+                        self.overwrite.push(planned_file);
+                    }
+                }
+            }
+        }
+        self.remove.extend(other.remove);
+        self.restore.extend(other.restore);
+        self.skip.extend(other.skip);
+        self.kept.extend(other.kept);
+        self.set_aside.extend(other.set_aside);
+        self.reassigned.extend(other.reassigned);
+        for dir in other.create_dirs {
+            if !self.create_dirs.contains(&dir) {
+                self.create_dirs.push(dir);
+            }
+        }
+    }
+
+    /// Bytes written into the game
+    pub fn total_bytes(&self) -> u64 {
+        self.create.iter().chain(&self.overwrite).chain(&self.restore).map(|planned_file| planned_file.size).sum()
+    }
+
+    /// The plan as text, the totals first and then every file by what happens to it
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{} files to create, {} to overwrite, {} to remove, {} to put back, {} folders to create, {} to write",
+            self.create.len(),
+            self.overwrite.len(),
+            self.remove.len(),
+            self.restore.len(),
+            self.create_dirs.len(),
+            format_bytes(self.total_bytes())
+        )];
+        let owned_by = |owner: &Option<String>| match owner {
+            Some(owner) => format!("from {}", owner),
+            None => "not from a mod, it is backed up".to_string(),
+        };
+        if !self.steps.is_empty() {
+            lines.push("Steps:".to_string());
+            lines.extend(self.steps.iter().map(|step| format!("  {}", step)));
+        }
+        let sections: [(&str, &Vec<PlannedFile>); 9] = [
+            ("Create:", &self.create),
+            ("Overwrite:", &self.overwrite),
+            ("Taken from mods that stay installed:", &self.taken),
+            ("Remove:", &self.remove),
+            ("Put back:", &self.restore),
+            ("Leave in place, changed since they were installed:", &self.kept),
+            ("Move into the kept backups:", &self.set_aside),
+            ("Hand over to the mods that overwrote them:", &self.reassigned),
+            ("Skip, another file of the archive only differs in case:", &self.skip),
+        ];
+        for (heading, planned_files) in sections {
+            if planned_files.is_empty() {
+                continue;
+            }
+            lines.push(heading.to_string());
+            for planned_file in planned_files {
+                let path = to_portable_string(&planned_file.path);
+                let size = format_bytes(planned_file.size);
+                if matches!(
+                    heading,
+                    "Overwrite:" | "Taken from mods that stay installed:" | "Hand over to the mods that overwrote them:"
+                ) {
+                    lines.push(format!("  {} ({}), now {}", path, size, owned_by(&planned_file.owner)));
+                } else {
+                    lines.push(format!("  {} ({})", path, size));
+                }
+            }
+        }
+        if !self.create_dirs.is_empty() {
+            lines.push("Create folders:".to_string());
+            lines.extend(self.create_dirs.iter().map(|dir| format!("  {}", to_portable_string(dir))));
+        }
+        lines
+    }
+}
+
+/// A plan shown to the user, the operation only runs once they confirm it
+#[derive(Debug, Clone)]
+pub struct PlanPreview {
+    pub plan: OperationPlan,
+    /// Dispatched again when the plan is confirmed
+    pub confirm: IoEvent,
+    /// First line of the plan on screen
+    pub scroll: u16,
+}
+
+impl PlanPreview {
+    pub fn new(plan: OperationPlan, confirm: IoEvent) -> Self {
+        Self { plan, confirm, scroll: 0 }
+    }
+}
+
+/// An uninstall worked out for its preview. Once confirmed its journal runs as is, as long as the
+/// mods it was worked out from did not change in the meantime
+#[derive(Debug, Clone)]
+pub struct PlannedUninstall {
+    pub depends_on: Vec<InstalledMod>,
+    pub journal: Journal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned_file(path: &str, size: u64, owner: Option<&str>) -> PlannedFile {
+        PlannedFile {
+            path: PathBuf::from(path),
+            size,
+            owner: owner.map(|owner| owner.to_string()),
+        }
+    }
+
+    #[test]
+    fn should_merge_steps_and_list_every_file() {
+        let mut uninstall = OperationPlan::new("Uninstall old.zip".to_string());
+        uninstall.remove.push(planned_file("r6/scripts/old.reds", 10, Some("old.zip")));
+        uninstall.restore.push(planned_file("bin/x64/game.ini", 100, None));
+        uninstall.keep(Path::new("r6/config/old.ini"), 12);
+        uninstall.set_aside(Path::new("r6/config/old.ini"), 8);
+        uninstall.reassign(Path::new("r6/tweaks/old.yaml"), 30, "patch.zip");
+        let mut install = OperationPlan::new("Install new.zip".to_string());
+        install.create.push(planned_file("archive/pc/mod/new.archive", 2048, None));
+        install.overwrite.push(planned_file("r6/tweaks/shared.yaml", 20, Some("other.zip")));
+        install.create_dirs.push(PathBuf::from("archive/pc/mod"));
+        install.skip(Path::new("r6/tweaks/Shared.yaml"), 20);
+
+        let mut other_install = OperationPlan::new("Install patch.zip".to_string());
+        other_install.create.push(planned_file("archive/pc/mod/new.archive", 30, None));
+
+        let mut switch = OperationPlan::new("Switch to testing".to_string());
+        switch.extend("Uninstall old.zip".to_string(), "old.zip", uninstall);
+        switch.extend("Install new.zip".to_string(), "new.zip", install);
+        assert!(switch.writes_over_steps(&other_install));
+        assert!(!switch.writes_over_steps(&OperationPlan::new("Uninstall old.zip".to_string())));
+        switch.extend("Install patch.zip".to_string(), "patch.zip", other_install);
+        switch.taken.push(planned_file("r6/tweaks/shared.yaml", 20, Some("other.zip")));
+        assert_eq!(switch.total_bytes(), 2198);
+        assert_eq!(
+            switch.lines(),
+            vec![
+                "1 files to create, 2 to overwrite, 1 to remove, 1 to put back, 1 folders to create, 2.1 KB to write",
+                "Steps:",
+                "  Uninstall old.zip",
+                "  Install new.zip",
+                "  Install patch.zip",
+                "Create:",
+                "  archive/pc/mod/new.archive (2.0 KB)",
+                "Overwrite:",
+                "  r6/tweaks/shared.yaml (20 B), now from other.zip",
+                "  archive/pc/mod/new.archive (30 B), now from new.zip",
+                "Taken from mods that stay installed:",
+                "  r6/tweaks/shared.yaml (20 B), now from other.zip",
+                "Remove:",
+                "  r6/scripts/old.reds (10 B)",
+                "Put back:",
+                "  bin/x64/game.ini (100 B)",
+                "Leave in place, changed since they were installed:",
+                "  r6/config/old.ini (12 B)",
+                "Move into the kept backups:",
+                "  r6/config/old.ini (8 B)",
+                "Hand over to the mods that overwrote them:",
+                "  r6/tweaks/old.yaml (30 B), now from patch.zip",
+                "Skip, another file of the archive only differs in case:",
+                "  r6/tweaks/Shared.yaml (20 B)",
+                "Create folders:",
+                "  archive/pc/mod",
+            ]
+        );
+    }
+}
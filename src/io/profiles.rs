@@ -0,0 +1,159 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::{fomod::FomodChoices, manifest::InstallManifest, paths::portable_path};
+
+/// A mod as it is installed in a profile
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProfileMod {
+    pub mod_name: String,
+    pub enabled: bool,
+    #[serde(default, with = "portable_path")]
+    pub archive_root: PathBuf,
+    #[serde(default)]
+    pub fomod_choices: Option<FomodChoices>,
+}
+
+/// A named set of installed mods, kept with the settings. Mods are listed in the order they
+/// were installed so files several mods ship end up with the same owner again
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    pub mods: Vec<ProfileMod>,
+    /// Archives of `archive/pc/mod` in load order
+    #[serde(default)]
+    pub load_order: Vec<String>,
+}
+
+impl Profile {
+    /// Record what is installed in the Cyberpunk folder right now
+    pub fn capture(name: String, manifest: &InstallManifest, load_order: Vec<String>) -> Self {
+        let mut installed_mods: Vec<_> = manifest.mods.values().collect();
+        installed_mods.sort_by_key(|installed_mod| installed_mod.installed_at);
+        Self {
+            name,
+            mods: installed_mods
+                .into_iter()
+                .map(|installed_mod| ProfileMod {
+                    mod_name: installed_mod.mod_name.clone(),
+                    enabled: !installed_mod.disabled,
+                    archive_root: installed_mod.archive_root.clone(),
+                    fomod_choices: installed_mod.fomod_choices.clone(),
+                })
+                .collect(),
+            load_order,
+        }
+    }
+}
+
+/// What has to change in the Cyberpunk folder to match a profile, applied in field order
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProfileSwitch {
+    pub uninstall: Vec<String>,
+    pub install: Vec<ProfileMod>,
+    pub enable: Vec<String>,
+    pub disable: Vec<String>,
+}
+
+impl ProfileSwitch {
+    /// Compare the manifest with a profile. Mods installed with other options or from another
+    /// folder of their archive are installed again
+    pub fn plan(manifest: &InstallManifest, profile: &Profile) -> Self {
+        let mut switch = ProfileSwitch::default();
+        for installed_mod in manifest.mods.values() {
+            let wanted = profile.mods.iter().find(|profile_mod| profile_mod.mod_name == installed_mod.mod_name);
+            let reinstall = wanted.is_some_and(|profile_mod| {
+                profile_mod.fomod_choices != installed_mod.fomod_choices || profile_mod.archive_root != installed_mod.archive_root
+            });
+            if wanted.is_none() || reinstall {
+                switch.uninstall.push(installed_mod.mod_name.clone());
+            }
+        }
+        for profile_mod in &profile.mods {
+            let installed = manifest
+                .get(&profile_mod.mod_name)
+                .filter(|_| !switch.uninstall.contains(&profile_mod.mod_name));
+            match installed {
+                Some(installed_mod) if installed_mod.disabled && profile_mod.enabled => {
+                    switch.enable.push(profile_mod.mod_name.clone())
+                }
+                Some(installed_mod) if !installed_mod.disabled && !profile_mod.enabled => {
+                    switch.disable.push(profile_mod.mod_name.clone())
+                }
+                Some(_) => {}
+                None => {
+                    switch.install.push(profile_mod.clone());
+                    if !profile_mod.enabled {
+                        switch.disable.push(profile_mod.mod_name.clone());
+                    }
+                }
+            }
+        }
+        switch
+    }
+
+    /// Whether the installed mods already match the profile
+    pub fn is_empty(&self) -> bool {
+        self.uninstall.is_empty() && self.install.is_empty() && self.enable.is_empty() && self.disable.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{fomod::FomodSelection, manifest::InstalledMod};
+
+    fn installed(mod_name: &str, disabled: bool) -> InstalledMod {
+        let mut installed_mod = InstalledMod::new(mod_name.to_string(), PathBuf::from(mod_name), String::new());
+        installed_mod.disabled = disabled;
+        installed_mod
+    }
+
+    fn profile_mod(mod_name: &str, enabled: bool) -> ProfileMod {
+        ProfileMod {
+            mod_name: mod_name.to_string(),
+            enabled,
+            archive_root: PathBuf::new(),
+            fomod_choices: None,
+        }
+    }
+
+    #[test]
+    fn should_only_plan_needed_changes() {
+        let mut manifest = InstallManifest::default();
+        manifest.insert(installed("kept.zip", false));
+        manifest.insert(installed("removed.zip", false));
+        manifest.insert(installed("disabled.zip", true));
+        let mut fomod_mod = installed("fomod.zip", false);
+        fomod_mod.fomod_choices = Some(FomodChoices::default());
+        manifest.insert(fomod_mod);
+
+        let mut other_choices = profile_mod("fomod.zip", true);
+        other_choices.fomod_choices = Some(FomodChoices {
+            selections: vec![FomodSelection {
+                step: "Main".to_string(),
+                group: "Color".to_string(),
+                plugins: vec!["Red".to_string()],
+            }],
+        });
+        let profile = Profile {
+            name: "Testing".to_string(),
+            mods: vec![
+                profile_mod("kept.zip", true),
+                profile_mod("disabled.zip", true),
+                other_choices.clone(),
+                profile_mod("new.zip", false),
+            ],
+            load_order: vec![],
+        };
+        let switch = ProfileSwitch::plan(&manifest, &profile);
+        assert_eq!(switch.uninstall, vec!["fomod.zip".to_string(), "removed.zip".to_string()]);
+        assert_eq!(switch.install, vec![other_choices, profile_mod("new.zip", false)]);
+        assert_eq!(switch.enable, vec!["disabled.zip".to_string()]);
+        assert_eq!(switch.disable, vec!["new.zip".to_string()]);
+
+        let current = Profile::capture("Current".to_string(), &manifest, vec![]);
+        assert!(ProfileSwitch::plan(&manifest, &current).is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use eyre::{eyre, Result};
+
+/// The step a long operation is at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressPhase {
+    Extract,
+    Copy,
+    Verify,
+}
+
+impl ProgressPhase {
+    pub fn to_str(&self) -> &str {
+        match self {
+            ProgressPhase::Extract => "Extracting",
+            ProgressPhase::Copy => "Copying",
+            ProgressPhase::Verify => "Verifying",
+        }
+    }
+}
+
+/// How far the current phase of an operation got. Totals are 0 when they are not known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: ProgressPhase,
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+impl Progress {
+    /// Done part of the phase between 0 and 1, by bytes when their total is known
+    pub fn ratio(&self) -> f64 {
+        let (done, total) = if self.bytes_total > 0 {
+            (self.bytes_done, self.bytes_total)
+        } else {
+            (self.files_done, self.files_total)
+        };
+        if total == 0 {
+            return 0.0;
+        }
+        (done as f64 / total as f64).min(1.0)
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = if self.files_total > 0 {
+            format!("{} {}/{} files", self.phase.to_str(), self.files_done, self.files_total)
+        } else {
+            format!("{} {} files", self.phase.to_str(), self.files_done)
+        };
+        if self.bytes_total > 0 {
+            label.push_str(&format!(", {}/{}", format_bytes(self.bytes_done), format_bytes(self.bytes_total)));
+        } else if self.bytes_done > 0 {
+            label.push_str(&format!(", {}", format_bytes(self.bytes_done)));
+        }
+        label
+    }
+}
+
+/// Progress of the running IO operation, shared with the UI so it can be drawn while the
+/// operation does not hold the app lock. The UI also uses it to ask the operation to stop
+#[derive(Debug, Clone, Default)]
+pub struct ProgressHandle {
+    progress: Arc<Mutex<Option<Progress>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ProgressHandle {
+    pub fn get(&self) -> Option<Progress> {
+        self.progress.lock().unwrap().clone()
+    }
+
+    /// Start a phase with nothing done yet
+    pub fn phase(&self, phase: ProgressPhase, files_total: u64, bytes_total: u64) {
+        *self.progress.lock().unwrap() = Some(Progress {
+            phase,
+            files_done: 0,
+            files_total,
+            bytes_done: 0,
+            bytes_total,
+        });
+    }
+
+    /// A file of the current phase is done
+    pub fn file_done(&self, bytes: u64) {
+        if let Some(progress) = self.progress.lock().unwrap().as_mut() {
+            progress.files_done += 1;
+            progress.bytes_done += bytes;
+        }
+    }
+
+    /// Ask the running operation to stop at its next check
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fails once the operation was cancelled, operations call it between steps
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(eyre!("Cancelled"));
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) {
+        *self.progress.lock().unwrap() = None;
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_report_progress_by_bytes_when_known() {
+        let handle = ProgressHandle::default();
+        handle.phase(ProgressPhase::Extract, 4, 0);
+        handle.file_done(1536);
+        let progress = handle.get().unwrap();
+        assert_eq!(progress.ratio(), 0.25);
+        assert_eq!(progress.label(), "Extracting 1/4 files, 1.5 KB");
+        handle.phase(ProgressPhase::Extract, 0, 0);
+        handle.file_done(10);
+        assert_eq!(handle.get().unwrap().label(), "Extracting 1 files, 10 B");
+
+        handle.phase(ProgressPhase::Copy, 2, 1000);
+        handle.file_done(750);
+        assert_eq!(handle.get().unwrap().ratio(), 0.75);
+        handle.cancel();
+        assert!(handle.check_cancelled().is_err());
+        handle.finish();
+        assert!(handle.get().is_none());
+        assert!(handle.check_cancelled().is_ok());
+    }
+}
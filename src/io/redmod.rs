@@ -0,0 +1,286 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{eyre, Result};
+use serde::Deserialize;
+use walkdir::WalkDir;
+
+use crate::constants::REDMOD_INFO_FILE_NAME;
+
+use super::{load_order::merge_load_order, manifest::InstallManifest, paths::CaseResolver};
+
+/// A sound a REDmod adds or replaces, declared in its `info.json`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CustomSound {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub sound_type: String,
+    /// Wav file in the `customSounds` folder of the mod, `None` for sounds that are muted
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+/// The `info.json` at the root of every REDmod folder
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RedmodInfo {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub custom_sounds: Vec<CustomSound>,
+}
+
+impl RedmodInfo {
+    pub fn parse(info_json: &str) -> Result<Self> {
+        // files saved by Windows editors often start with a byte order mark
+        Ok(serde_json::from_str(info_json.trim_start_matches('\u{feff}'))?)
+    }
+
+    /// Read the `info.json` of a REDmod folder, whatever its casing
+    pub fn load(redmod_dir: &Path) -> Result<Self> {
+        let info_path = redmod_dir.join(CaseResolver::new(redmod_dir).resolve(Path::new(REDMOD_INFO_FILE_NAME)));
+        let info_json = fs::read(&info_path).map_err(|err| eyre!("{}: {}", info_path.to_string_lossy(), err))?;
+        Self::parse(&String::from_utf8_lossy(&info_json))
+    }
+}
+
+/// A folder of `mods/`, or one parked in the data folder while it is disabled
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redmod {
+    pub folder: String,
+    pub enabled: bool,
+    /// `None` when `info.json` could not be read
+    pub info: Option<RedmodInfo>,
+    /// The mod of the manifest that installed it, `None` when it was installed by hand
+    pub installed_by: Option<String>,
+}
+
+impl Redmod {
+    pub fn display_name(&self) -> &str {
+        self.info.as_ref().map(|info| info.name.as_str()).unwrap_or(&self.folder)
+    }
+}
+
+/// `mods/` with the casing it has in the Cyberpunk folder, relative to it
+pub fn redmods_dir(cyberpunk_dir: &Path) -> PathBuf {
+    CaseResolver::new(cyberpunk_dir).resolve(Path::new("mods"))
+}
+
+/// Folders holding an `info.json`
+fn redmod_folders(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut folders: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter(|entry| {
+            let folder = entry.path();
+            folder.join(CaseResolver::new(&folder).resolve(Path::new(REDMOD_INFO_FILE_NAME))).is_file()
+        })
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    folders.sort_by_key(|folder| folder.to_lowercase());
+    folders
+}
+
+/// Every REDmod of the Cyberpunk folder, enabled or not, in deploy order. The order and the
+/// disabled folders recorded in the manifest are brought up to date with what is on disk
+pub fn find_redmods(cyberpunk_dir: &Path, manifest: &mut InstallManifest) -> Vec<Redmod> {
+    let mods_dir = redmods_dir(cyberpunk_dir);
+    let enabled_dir = cyberpunk_dir.join(&mods_dir);
+    let disabled_dir = InstallManifest::disabled_redmods_dir(cyberpunk_dir);
+    let enabled = redmod_folders(&enabled_dir);
+    let disabled: Vec<String> = redmod_folders(&disabled_dir)
+        .into_iter()
+        .filter(|folder| !enabled.contains(folder))
+        .collect();
+    let mut present = enabled.clone();
+    present.extend(disabled.iter().cloned());
+    manifest.redmod_order = merge_load_order(&manifest.redmod_order, &present);
+    manifest.disabled_redmods = disabled.clone();
+    let owners = manifest.owners();
+    manifest
+        .redmod_order
+        .iter()
+        .map(|folder| {
+            let is_enabled = enabled.contains(folder);
+            let dir = if is_enabled { &enabled_dir } else { &disabled_dir };
+            let info_json = mods_dir.join(folder).join(REDMOD_INFO_FILE_NAME);
+            Redmod {
+                folder: folder.clone(),
+                enabled: is_enabled,
+                info: RedmodInfo::load(&dir.join(folder)).ok(),
+                installed_by: owners.owner_of(&info_json).map(|mod_name| mod_name.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Move a REDmod folder in or out of `mods/`, the game only deploys what is in there
+pub fn set_redmod_enabled(cyberpunk_dir: &Path, manifest: &mut InstallManifest, folder: &str, enabled: bool) -> Result<()> {
+    let enabled_path = cyberpunk_dir.join(redmods_dir(cyberpunk_dir)).join(folder);
+    let disabled_path = InstallManifest::disabled_redmods_dir(cyberpunk_dir).join(folder);
+    let (from, to) = if enabled {
+        (disabled_path, enabled_path)
+    } else {
+        (enabled_path, disabled_path)
+    };
+    if to.exists() {
+        return Err(eyre!("{} already exists", to.to_string_lossy()));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // both folders are inside the Cyberpunk folder so this is a single rename
+    fs::rename(from, to)?;
+    manifest.disabled_redmods.retain(|disabled| disabled != folder);
+    if !enabled {
+        manifest.disabled_redmods.push(folder.to_string());
+    }
+    Ok(())
+}
+
+/// Drop disabled REDmods whose files were all uninstalled, their parked folders are left empty
+pub fn forget_empty_redmods(cyberpunk_dir: &Path, manifest: &mut InstallManifest) -> Result<()> {
+    let disabled_dir = InstallManifest::disabled_redmods_dir(cyberpunk_dir);
+    for folder in manifest.disabled_redmods.clone() {
+        let parked_dir = disabled_dir.join(&folder);
+        let has_files = WalkDir::new(&parked_dir)
+            .into_iter()
+            .flatten()
+            .any(|entry| entry.file_type().is_file());
+        if !has_files {
+            if parked_dir.exists() {
+                fs::remove_dir_all(parked_dir)?;
+            }
+            manifest.disabled_redmods.retain(|disabled| *disabled != folder);
+            manifest.redmod_order.retain(|redmod| *redmod != folder);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use super::*;
+    use crate::io::manifest::{InstalledFile, InstalledMod};
+
+    fn game_dir(name: &str) -> PathBuf {
+        let dir = temp_dir().join("cyberpunk_mod_manager_tests").join(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(dir.join("mods")).unwrap();
+        dir
+    }
+
+    fn write_redmod(dir: &Path, folder: &str) {
+        fs::create_dir_all(dir.join(folder)).unwrap();
+        fs::write(dir.join(folder).join(REDMOD_INFO_FILE_NAME), format!("{{ \"name\": \"{} mod\" }}", folder)).unwrap();
+    }
+
+    #[test]
+    fn should_parse_redmod_info() {
+        let info = RedmodInfo::parse(
+            "\u{feff}{
+                \"name\": \"my_redmod\",
+                \"version\": \"1.2.0\",
+                \"description\": \"Louder guns\",
+                \"customSounds\": [
+                    { \"name\": \"w_gun_pistol_power_unity_fire\", \"type\": \"mod_sfx_2d\", \"file\": \"unity.wav\", \"gain\": 1.0 },
+                    { \"name\": \"w_gun_npc_fire\", \"type\": \"mod_skip\" }
+                ]
+            }",
+        )
+        .unwrap();
+        assert_eq!(info.name, "my_redmod");
+        assert_eq!(info.version, "1.2.0");
+        assert_eq!(info.custom_sounds.len(), 2);
+        assert_eq!(info.custom_sounds[0].file.as_deref(), Some("unity.wav"));
+        assert_eq!(info.custom_sounds[1].sound_type, "mod_skip");
+
+        let minimal = RedmodInfo::parse("{ \"name\": \"minimal\" }").unwrap();
+        assert!(minimal.custom_sounds.is_empty());
+    }
+
+    #[test]
+    fn should_merge_enabled_and_disabled_redmods_in_order() {
+        let cyberpunk_dir = game_dir("find_redmods");
+        write_redmod(&cyberpunk_dir.join("mods"), "Beta");
+        write_redmod(&cyberpunk_dir.join("mods"), "alpha");
+        // CET keeps its own mods in folders without info.json
+        fs::create_dir_all(cyberpunk_dir.join("mods").join("not_a_redmod")).unwrap();
+        write_redmod(&InstallManifest::disabled_redmods_dir(&cyberpunk_dir), "gamma");
+        let mut manifest = InstallManifest {
+            redmod_order: vec!["gamma".to_string(), "gone".to_string(), "beta".to_string()],
+            ..Default::default()
+        };
+        let mut installed_mod = InstalledMod::new("alpha.zip".to_string(), PathBuf::from("alpha.zip"), String::new());
+        installed_mod.files.push(InstalledFile {
+            path: Path::new("mods").join("alpha").join(REDMOD_INFO_FILE_NAME),
+            size: 0,
+            hash: String::new(),
+            backed_up: false,
+            previous_owner: None,
+            disabled: false,
+        });
+        manifest.insert(installed_mod);
+
+        let redmods = find_redmods(&cyberpunk_dir, &mut manifest);
+        let folders: Vec<(&str, bool)> = redmods.iter().map(|redmod| (redmod.folder.as_str(), redmod.enabled)).collect();
+        assert_eq!(folders, vec![("gamma", false), ("Beta", true), ("alpha", true)]);
+        assert_eq!(manifest.redmod_order, vec!["gamma", "Beta", "alpha"]);
+        assert_eq!(manifest.disabled_redmods, vec!["gamma"]);
+        assert_eq!(redmods[0].display_name(), "gamma mod");
+        assert_eq!(redmods[2].installed_by.as_deref(), Some("alpha.zip"));
+        assert_eq!(redmods[1].installed_by, None);
+    }
+
+    #[test]
+    fn should_move_redmods_out_of_mods_and_back() {
+        let cyberpunk_dir = game_dir("set_redmod_enabled");
+        write_redmod(&cyberpunk_dir.join("mods"), "alpha");
+        let mut manifest = InstallManifest::default();
+
+        set_redmod_enabled(&cyberpunk_dir, &mut manifest, "alpha", false).unwrap();
+        assert!(!cyberpunk_dir.join("mods").join("alpha").exists());
+        assert!(InstallManifest::disabled_redmods_dir(&cyberpunk_dir).join("alpha").join(REDMOD_INFO_FILE_NAME).is_file());
+        assert_eq!(manifest.disabled_redmods, vec!["alpha"]);
+
+        // a folder of the same name installed meanwhile is never overwritten
+        write_redmod(&cyberpunk_dir.join("mods"), "alpha");
+        assert!(set_redmod_enabled(&cyberpunk_dir, &mut manifest, "alpha", true).is_err());
+        fs::remove_dir_all(cyberpunk_dir.join("mods").join("alpha")).unwrap();
+
+        set_redmod_enabled(&cyberpunk_dir, &mut manifest, "alpha", true).unwrap();
+        assert!(cyberpunk_dir.join("mods").join("alpha").join(REDMOD_INFO_FILE_NAME).is_file());
+        assert!(manifest.disabled_redmods.is_empty());
+    }
+
+    #[test]
+    fn should_forget_disabled_redmods_left_empty() {
+        let cyberpunk_dir = game_dir("forget_empty_redmods");
+        let disabled_dir = InstallManifest::disabled_redmods_dir(&cyberpunk_dir);
+        write_redmod(&disabled_dir, "kept");
+        fs::create_dir_all(disabled_dir.join("uninstalled").join("archives")).unwrap();
+        let mut manifest = InstallManifest {
+            redmod_order: vec!["uninstalled".to_string(), "kept".to_string()],
+            disabled_redmods: vec!["uninstalled".to_string(), "kept".to_string()],
+            ..Default::default()
+        };
+
+        forget_empty_redmods(&cyberpunk_dir, &mut manifest).unwrap();
+        assert!(!disabled_dir.join("uninstalled").exists());
+        assert!(disabled_dir.join("kept").is_dir());
+        assert_eq!(manifest.disabled_redmods, vec!["kept"]);
+        assert_eq!(manifest.redmod_order, vec!["kept"]);
+    }
+}
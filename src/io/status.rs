@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::Result;
+
+use super::manifest::{hash_file, InstallManifest, OwnerIndex};
+
+/// How much of a mod is present in the Cyberpunk folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModInstallStatus {
+    Installed,
+    /// Some recorded files are gone
+    PartiallyInstalled { missing: Vec<PathBuf> },
+    /// Some files differ from what the archive contained
+    Modified { changed: Vec<PathBuf> },
+    /// Some files were overwritten by other mods
+    Superseded { by: Vec<String> },
+    /// Installed but its files are kept out of the game
+    Disabled,
+    NotInstalled,
+}
+
+impl ModInstallStatus {
+    pub fn to_str(&self) -> &str {
+        match self {
+            ModInstallStatus::Installed => "Installed",
+            ModInstallStatus::PartiallyInstalled { .. } => "Partially installed",
+            ModInstallStatus::Modified { .. } => "Modified",
+            ModInstallStatus::Superseded { .. } => "Superseded",
+            ModInstallStatus::Disabled => "Disabled",
+            ModInstallStatus::NotInstalled => "Not installed",
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        !matches!(self, ModInstallStatus::NotInstalled)
+    }
+
+    /// Lines explaining the status, empty when there is nothing to add
+    pub fn details(&self) -> Vec<String> {
+        match self {
+            ModInstallStatus::PartiallyInstalled { missing } => missing
+                .iter()
+                .map(|path| format!("Missing: {}", path.to_string_lossy()))
+                .collect(),
+            ModInstallStatus::Modified { changed } => changed
+                .iter()
+                .map(|path| format!("Modified: {}", path.to_string_lossy()))
+                .collect(),
+            ModInstallStatus::Superseded { by } => by
+                .iter()
+                .map(|mod_name| format!("Overwritten by: {}", mod_name))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Work out the install status of a mod from the manifest. Without `verify_hashes` only
+/// presence and sizes are compared, which is cheap enough to run for every mod in the list
+pub fn get_mod_install_status(
+    manifest: &InstallManifest,
+    cyberpunk_dir: &Path,
+    mod_name: &str,
+    verify_hashes: bool,
+) -> Result<ModInstallStatus> {
+    mod_install_status(manifest, &manifest.owners(), cyberpunk_dir, mod_name, verify_hashes)
+}
+
+fn mod_install_status(
+    manifest: &InstallManifest,
+    owners: &OwnerIndex,
+    cyberpunk_dir: &Path,
+    mod_name: &str,
+    verify_hashes: bool,
+) -> Result<ModInstallStatus> {
+    let installed_mod = match manifest.get(mod_name) {
+        Some(installed_mod) => installed_mod,
+        None => return Ok(ModInstallStatus::NotInstalled),
+    };
+    let mut missing = vec![];
+    let mut changed = vec![];
+    let mut superseded_by: Vec<String> = vec![];
+    for installed_file in &installed_mod.files {
+        let dest_path = manifest.location_of(cyberpunk_dir, mod_name, installed_file);
+        if !dest_path.is_file() {
+            missing.push(installed_file.path.clone());
+            continue;
+        }
+        if installed_file.disabled {
+            continue;
+        }
+        if let Some(owner) = owners.owner_of(&installed_file.path).filter(|owner| *owner != mod_name) {
+            if !superseded_by.iter().any(|mod_name| mod_name == owner) {
+                superseded_by.push(owner.to_string());
+            }
+            continue;
+        }
+        let size_differs = fs::metadata(&dest_path)?.len() != installed_file.size;
+        if size_differs || (verify_hashes && hash_file(&dest_path)? != installed_file.hash) {
+            changed.push(installed_file.path.clone());
+        }
+    }
+    Ok(if !missing.is_empty() {
+        ModInstallStatus::PartiallyInstalled { missing }
+    } else if installed_mod.disabled {
+        ModInstallStatus::Disabled
+    } else if !changed.is_empty() {
+        ModInstallStatus::Modified { changed }
+    } else if !superseded_by.is_empty() {
+        ModInstallStatus::Superseded { by: superseded_by }
+    } else {
+        ModInstallStatus::Installed
+    })
+}
+
+/// Quick statuses for every mod in the mod folder
+pub fn get_mod_install_statuses(
+    manifest: &InstallManifest,
+    cyberpunk_dir: &Path,
+    mod_names: &[String],
+) -> Result<HashMap<String, ModInstallStatus>> {
+    let owners = manifest.owners();
+    let mut mod_statuses = HashMap::new();
+    for mod_name in mod_names {
+        mod_statuses.insert(mod_name.clone(), mod_install_status(manifest, &owners, cyberpunk_dir, mod_name, false)?);
+    }
+    Ok(mod_statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use super::*;
+    use crate::io::manifest::{InstallManifest, InstalledFile, InstalledMod};
+
+    fn game_dir(name: &str) -> PathBuf {
+        let dir = temp_dir().join("cyberpunk_mod_manager_tests").join(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(dir.join("archive")).unwrap();
+        dir
+    }
+
+    /// Install `files` for `mod_name` straight into the game folder and record them
+    fn install(cyberpunk_dir: &Path, manifest: &mut InstallManifest, mod_name: &str, files: &[(&str, Option<&str>)]) {
+        let mut installed_mod = InstalledMod::new(mod_name.to_string(), PathBuf::from(mod_name), String::new());
+        for (path, previous_owner) in files {
+            let dest_path = cyberpunk_dir.join(path);
+            fs::write(&dest_path, mod_name).unwrap();
+            installed_mod.files.push(InstalledFile {
+                path: PathBuf::from(path),
+                size: fs::metadata(&dest_path).unwrap().len(),
+                hash: hash_file(&dest_path).unwrap(),
+                backed_up: false,
+                previous_owner: previous_owner.map(|owner| owner.to_string()),
+                disabled: false,
+            });
+        }
+        manifest.insert(installed_mod);
+    }
+
+    fn status(cyberpunk_dir: &Path, manifest: &InstallManifest, mod_name: &str) -> ModInstallStatus {
+        get_mod_install_status(manifest, cyberpunk_dir, mod_name, true).unwrap()
+    }
+
+    #[test]
+    fn should_report_installed_and_not_installed_mods() {
+        let cyberpunk_dir = game_dir("status_installed");
+        let mut manifest = InstallManifest::default();
+        install(&cyberpunk_dir, &mut manifest, "a.zip", &[("archive/a.archive", None)]);
+
+        assert_eq!(status(&cyberpunk_dir, &manifest, "a.zip"), ModInstallStatus::Installed);
+        assert_eq!(status(&cyberpunk_dir, &manifest, "b.zip"), ModInstallStatus::NotInstalled);
+    }
+
+    #[test]
+    fn should_report_missing_and_modified_files() {
+        let cyberpunk_dir = game_dir("status_missing_modified");
+        let mut manifest = InstallManifest::default();
+        install(&cyberpunk_dir, &mut manifest, "a.zip", &[("archive/a.archive", None), ("archive/b.archive", None)]);
+
+        // same size, only the hash check notices
+        fs::write(cyberpunk_dir.join("archive/a.archive"), "b.zip").unwrap();
+        assert_eq!(
+            status(&cyberpunk_dir, &manifest, "a.zip"),
+            ModInstallStatus::Modified { changed: vec![PathBuf::from("archive/a.archive")] }
+        );
+        assert_eq!(
+            get_mod_install_status(&manifest, &cyberpunk_dir, "a.zip", false).unwrap(),
+            ModInstallStatus::Installed
+        );
+
+        fs::remove_file(cyberpunk_dir.join("archive/b.archive")).unwrap();
+        assert_eq!(
+            status(&cyberpunk_dir, &manifest, "a.zip"),
+            ModInstallStatus::PartiallyInstalled { missing: vec![PathBuf::from("archive/b.archive")] }
+        );
+    }
+
+    #[test]
+    fn should_report_mods_overwritten_by_others() {
+        let cyberpunk_dir = game_dir("status_superseded");
+        let mut manifest = InstallManifest::default();
+        install(&cyberpunk_dir, &mut manifest, "a.zip", &[("archive/shared.archive", None)]);
+        install(&cyberpunk_dir, &mut manifest, "b.zip", &[("archive/shared.archive", Some("a.zip"))]);
+
+        assert_eq!(
+            status(&cyberpunk_dir, &manifest, "a.zip"),
+            ModInstallStatus::Superseded { by: vec!["b.zip".to_string()] }
+        );
+        assert_eq!(status(&cyberpunk_dir, &manifest, "b.zip"), ModInstallStatus::Installed);
+    }
+
+    #[test]
+    fn should_report_disabled_mods() {
+        let cyberpunk_dir = game_dir("status_disabled");
+        let mut manifest = InstallManifest::default();
+        install(&cyberpunk_dir, &mut manifest, "a.zip", &[("archive/a.archive", None)]);
+        let disabled_path = InstallManifest::disabled_location(&cyberpunk_dir, "a.zip", Path::new("archive/a.archive"));
+        fs::create_dir_all(disabled_path.parent().unwrap()).unwrap();
+        fs::rename(cyberpunk_dir.join("archive/a.archive"), disabled_path).unwrap();
+        let installed_mod = manifest.mods.get_mut("a.zip").unwrap();
+        installed_mod.disabled = true;
+        installed_mod.files[0].disabled = true;
+
+        assert_eq!(status(&cyberpunk_dir, &manifest, "a.zip"), ModInstallStatus::Disabled);
+    }
+}
@@ -43,7 +43,7 @@ pub async fn start_ui(app: &Arc<tokio::sync::Mutex<App>>) -> Result<()> {
         let mut app = app.lock().await;
         let mut states = app.state.clone();
         // Render
-        terminal.draw(|rect| ui::draw(rect, &mut app, &mut states))?;
+        terminal.draw(|rect| ui::draw(rect, &app, &mut states))?;
 
         // Handle inputs
         let result = match events.next().await {
@@ -4,17 +4,14 @@ use eyre::Result;
 use log::LevelFilter;
 use cyberpunk_mod_manager::{
     app::App,
-    io::{
-        handler::IoAsyncHandler,
-        IoEvent
-    }
+    io::jobs::{run_jobs, JobEvent}
 };
 use cyberpunk_mod_manager::start_ui;
 
 #[tokio::main]
 async fn main() -> Result<()> {
 
-    let (sync_io_tx, mut sync_io_rx) = tokio::sync::mpsc::channel::<IoEvent>(100);
+    let (sync_io_tx, sync_io_rx) = tokio::sync::mpsc::channel::<JobEvent>(100);
 
     // We need to share the App between thread
     let app = Arc::new(tokio::sync::Mutex::new(App::new(sync_io_tx.clone())));
@@ -25,12 +22,7 @@ async fn main() -> Result<()> {
     tui_logger::set_default_level(log::LevelFilter::Info);
 
     // Handle IO in a specifc thread
-    tokio::spawn(async move {
-        let mut handler = IoAsyncHandler::new(app);
-        while let Some(io_event) = sync_io_rx.recv().await {
-            handler.handle_io_event(io_event).await;
-        }
-    });
+    tokio::spawn(run_jobs(app, sync_io_rx));
 
     start_ui(&app_ui).await?;
 
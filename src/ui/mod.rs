@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod ui;
 use tui::backend::Backend;
 use tui::Frame;
@@ -7,7 +8,7 @@ use crate::{app::{
     state::{AppState, UiMode}}
 };
 
-use self::ui::{check_size, draw_size_error, draw_explore, draw_select_folder, draw_mod_popup};
+use self::ui::{check_size, draw_size_error, draw_explore, draw_select_folder, draw_mod_popup, draw_choice_popup, draw_plan_preview, draw_fomod_wizard, draw_frameworks, draw_jobs, draw_load_order, draw_redmods, draw_profiles};
 
 /// Main UI Drawing handler
 pub fn draw<B>(rect: &mut Frame<B>, app: &App, states: &mut AppState)
@@ -26,9 +27,35 @@ where
             if app.mod_popup.is_some() {
                 draw_mod_popup(rect, app, &mut states.mod_options.state);
             }
+            if app.fomod_wizard.is_some() {
+                draw_fomod_wizard(rect, app);
+            }
         }
         UiMode::SelectFolder => {
             draw_select_folder(rect, app)
         }
+        UiMode::Frameworks => {
+            draw_frameworks(rect, app)
+        }
+        UiMode::LoadOrder => {
+            draw_load_order(rect, app, &mut states.load_order.state)
+        }
+        UiMode::Redmods => {
+            draw_redmods(rect, app, &mut states.redmods.state)
+        }
+        UiMode::Profiles => {
+            draw_profiles(rect, app, &mut states.profiles.state)
+        }
+        UiMode::Jobs => {
+            draw_jobs(rect, app)
+        }
+    }
+
+    // questions for the user are always drawn on top
+    if app.choice_popup.is_some() {
+        draw_choice_popup(rect, app);
+    }
+    if app.plan_preview.is_some() {
+        draw_plan_preview(rect, app);
     }
 }
\ No newline at end of file
@@ -1,10 +1,10 @@
-use std::path::PathBuf;
 use tui::{
     layout::{Rect, Layout, Direction, Constraint, Alignment},
+    style::Style,
     backend::Backend,
     Frame,
     text::{Spans, Span, Text},
-    widgets::{Paragraph, Block, Borders, Wrap, ListItem, List, ListState, Clear}
+    widgets::{Paragraph, Block, Borders, Wrap, ListItem, List, ListState, Clear, Gauge}
 };
 use tui_logger::TuiLoggerWidget;
 
@@ -18,6 +18,7 @@ use crate::{
                 CYBERPUNK_STYLE_YELLOW_DARK, CYBERPUNK_STYLE_PINK_DARK, CYBERPUNK_STYLE_CYAN_DARK
     },
     App, app::{state::{Focus, AppStatus}, utils::ModOptions},
+    io::{frameworks::find_framework_archive, jobs::JobState, status::ModInstallStatus},
 };
 
 /// Helper function to check terminal size
@@ -142,12 +143,12 @@ pub fn draw_select_folder<B: Backend>(f: &mut Frame<B>, app: &App) {
     // check if input mode is active, if so, show cursor
     if app.state.status == AppStatus::UserInput && app.state.focus == Focus::ModFolderInput {
         f.set_cursor(
-            chunks[1].x + app.state.cursor_position.unwrap_or_else(||0) as u16 + 1,
+            chunks[1].x + app.state.cursor_position.unwrap_or(0) as u16 + 1,
             chunks[1].y + 1,
         );
     } else if app.state.status == AppStatus::UserInput && app.state.focus == Focus::CyberpunkFolderInput {
         f.set_cursor(
-            chunks[2].x + app.state.cursor_position.unwrap_or_else(||0) as u16 + 1,
+            chunks[2].x + app.state.cursor_position.unwrap_or(0) as u16 + 1,
             chunks[2].y + 1,
         );
     }
@@ -180,7 +181,7 @@ pub fn draw_explore<B: Backend>(f: &mut Frame<B>, app: &App, file_list_state: &m
 
     let title_widget = draw_title(app.mod_popup.is_some());
     
-    let current_folder = app.mod_folder.clone().unwrap_or_else(|| PathBuf::new());
+    let current_folder = app.mod_folder.clone().unwrap_or_default();
     // check if current folder is a directory if not set it to No folder selected
     let current_folder_string = if current_folder.is_dir() {
         current_folder.to_string_lossy().to_string()
@@ -197,7 +198,7 @@ pub fn draw_explore<B: Backend>(f: &mut Frame<B>, app: &App, file_list_state: &m
         .style(current_folder_widget_style)
         .wrap(Wrap { trim: true });
 
-    let cyberpunk_folder = app.cyberpunk_folder.clone().unwrap_or_else(|| PathBuf::new());
+    let cyberpunk_folder = app.cyberpunk_folder.clone().unwrap_or_default();
     // check if current folder is a directory if not set it to No folder selected
     let cyberpunk_folder_string = if cyberpunk_folder.is_dir() {
         cyberpunk_folder.to_string_lossy().to_string()
@@ -220,7 +221,18 @@ pub fn draw_explore<B: Backend>(f: &mut Frame<B>, app: &App, file_list_state: &m
         .items
         .iter()
         .map(|(name, _size)| {
-            ListItem::new(Text::from(name.clone()))
+            let mut spans = vec![];
+            if app.state.marked.contains(name) {
+                spans.push(Span::styled("[*] ", CYBERPUNK_STYLE_PINK));
+            }
+            spans.push(Span::raw(name.clone()));
+            if let Some(classification) = app.mod_types.get(name) {
+                spans.push(Span::styled(format!(" <{}>", classification.label()), LOG_DEBUG_STYLE));
+            }
+            if let Some(status) = app.mod_statuses.get(name).filter(|status| status.is_installed()) {
+                spans.push(Span::styled(format!(" [{}]", status.to_str()), install_status_style(status)));
+            }
+            ListItem::new(Spans::from(spans))
         })
         .collect();
 
@@ -230,9 +242,17 @@ pub fn draw_explore<B: Backend>(f: &mut Frame<B>, app: &App, file_list_state: &m
         CYBERPUNK_STYLE_CYAN
     };
 
+    let mut items_title = match app.state.type_filter {
+        Some(mod_type) => format!("Available files ({} only, <t> to change)", mod_type.to_str()),
+        None => "Available files".to_string(),
+    };
+    if !app.state.marked.is_empty() {
+        items_title.push_str(&format!(" - {} marked, <Enter> for batch actions", app.state.marked.len()));
+    }
+
     // Create a List from all list items and highlight the currently selected one
     let items_list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Available files"))
+        .block(Block::default().borders(Borders::ALL).title(items_title))
         .highlight_style(current_folder_widget_style)
         .highlight_symbol(">> ")
         .style(item_list_style);
@@ -309,12 +329,23 @@ pub fn draw_mod_popup<B: Backend>(f: &mut Frame<B>, app: &App, mod_options_state
         )
         .split(popup_area);
         
+    let name_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(chunks[0]);
+
     let mod_name = app.mod_popup.as_ref().unwrap().get_mod_name();
     let mod_name_widget = Paragraph::new(Text::raw(mod_name))
         .block(Block::default().borders(Borders::ALL).title("Mod Name"))
         .style(CYBERPUNK_STYLE_YELLOW)
         .wrap(Wrap { trim: true });
 
+    let mod_type = app.mod_types.get(mod_name).map(|classification| classification.label()).unwrap_or_default();
+    let mod_type_widget = Paragraph::new(Text::raw(mod_type))
+        .block(Block::default().borders(Borders::ALL).title("Mod Type"))
+        .style(CYBERPUNK_STYLE_CYAN)
+        .wrap(Wrap { trim: true });
+
     let items: Vec<ListItem> = ModOptions::get_all_options()
         .iter()
         .map(|mod_option| {
@@ -327,22 +358,553 @@ pub fn draw_mod_popup<B: Backend>(f: &mut Frame<B>, app: &App, mod_options_state
         .highlight_symbol(">> ")
         .style(CYBERPUNK_STYLE_CYAN);
 
-    let mod_install_status_bool = app.mod_popup.as_ref().unwrap().get_mod_install_status();
-    let mod_install_status = if mod_install_status_bool.is_none() {
-        "Checking...".to_string()
+    let mod_install_status = app.mod_popup.as_ref().unwrap().get_mod_install_status();
+    let (mod_install_status_text, mod_install_status_style) = match mod_install_status {
+        Some(status) => (status.to_str().to_string(), install_status_style(status)),
+        None => ("Checking...".to_string(), CYBERPUNK_STYLE_YELLOW),
+    };
+    let mod_install_status_widget = Paragraph::new(Text::raw(mod_install_status_text))
+        .block(Block::default().borders(Borders::ALL).title("Mod Install Status"))
+        .style(mod_install_status_style)
+        .wrap(Wrap { trim: true });
+
+    // show the files other mods already own, or what is wrong with the install, next to the options
+    let conflicts = &app.mod_popup.as_ref().unwrap().conflicts;
+    let status_details = mod_install_status.map(|status| status.details()).unwrap_or_default();
+    let missing_frameworks = &app.mod_popup.as_ref().unwrap().missing_frameworks;
+    let options_chunks = if conflicts.is_empty() && missing_frameworks.is_empty() && status_details.is_empty() {
+        vec![chunks[1]]
     } else {
-        if mod_install_status_bool.unwrap() {
-            "Installed".to_string()
-        } else {
-            "Not Installed".to_string()
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(chunks[1])
+    };
+
+    f.render_widget(mod_name_widget, name_chunks[0]);
+    f.render_widget(mod_type_widget, name_chunks[1]);
+    f.render_stateful_widget(items_list, options_chunks[0], mod_options_state);
+    if !conflicts.is_empty() {
+        let conflict_items: Vec<ListItem> = conflicts
+            .iter()
+            .map(|conflict| {
+                ListItem::new(Text::from(format!("{} ({})", conflict.path.to_string_lossy(), conflict.owner)))
+            })
+            .collect();
+        let conflicts_list = List::new(conflict_items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Conflicting files ({})", conflicts.len())))
+            .style(ERROR_TEXT_STYLE);
+        f.render_widget(conflicts_list, options_chunks[1]);
+    } else if !missing_frameworks.is_empty() {
+        let framework_items: Vec<ListItem> = missing_frameworks
+            .iter()
+            .map(|framework| {
+                let text = match find_framework_archive(&app.mod_types, *framework) {
+                    Some(framework_archive) => format!("{} (found {})", framework.to_str(), framework_archive),
+                    None => framework.to_str().to_string(),
+                };
+                ListItem::new(Text::from(text))
+            })
+            .collect();
+        let frameworks_list = List::new(framework_items)
+            .block(Block::default().borders(Borders::ALL).title(format!("Missing frameworks ({})", missing_frameworks.len())))
+            .style(LOG_WARN_STYLE);
+        f.render_widget(frameworks_list, options_chunks[1]);
+    } else if !status_details.is_empty() {
+        let detail_items: Vec<ListItem> = status_details
+            .iter()
+            .map(|detail| ListItem::new(Text::from(detail.clone())))
+            .collect();
+        let details_list = List::new(detail_items)
+            .block(Block::default().borders(Borders::ALL).title("Install Details"))
+            .style(mod_install_status_style);
+        f.render_widget(details_list, options_chunks[1]);
+    }
+    // a running install, uninstall or repair replaces the status until it is done
+    match app.progress.get().filter(|_| app.is_loading()) {
+        Some(progress) => {
+            let progress_gauge = Gauge::default()
+                .block(Block::default().borders(Borders::ALL).title("Progress"))
+                .gauge_style(CYBERPUNK_STYLE_CYAN)
+                .ratio(progress.ratio())
+                .label(progress.label());
+            f.render_widget(progress_gauge, chunks[2]);
+        }
+        None => f.render_widget(mod_install_status_widget, chunks[2]),
+    }
+}
+
+/// Colour of an install status, green when everything is fine and red when the mod needs attention
+pub fn install_status_style(status: &ModInstallStatus) -> Style {
+    match status {
+        ModInstallStatus::Installed => FOCUS_STYLE,
+        ModInstallStatus::NotInstalled => CYBERPUNK_STYLE_YELLOW,
+        ModInstallStatus::Superseded { .. } | ModInstallStatus::Disabled => LOG_WARN_STYLE,
+        _ => ERROR_TEXT_STYLE,
+    }
+}
+
+pub fn draw_choice_popup<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let choice_popup = app.choice_popup.as_ref().unwrap();
+    let popup_area = centered_rect(60, 50, f.size());
+    f.render_widget(Clear, popup_area);
+    f.render_widget(Block::default()
+        .borders(Borders::ALL)
+        .border_style(CYBERPUNK_STYLE_PINK)
+        .title(choice_popup.title.clone()), popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints(
+            [
+                Constraint::Percentage(60),
+                Constraint::Percentage(40),
+            ]
+            .as_ref(),
+        )
+        .split(popup_area);
+
+    let message: Vec<Spans> = choice_popup.message
+        .iter()
+        .map(|line| Spans::from(Span::raw(line.clone())))
+        .collect();
+    let message_widget = Paragraph::new(message)
+        .block(Block::default().borders(Borders::ALL))
+        .style(CYBERPUNK_STYLE_YELLOW)
+        .wrap(Wrap { trim: true });
+
+    let items: Vec<ListItem> = choice_popup.choices
+        .items
+        .iter()
+        .map(|(label, _)| ListItem::new(Text::from(label.clone())))
+        .collect();
+    let choices_list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("<Enter> to choose, <Esc> to dismiss"))
+        .highlight_style(CYBERPUNK_STYLE_PINK)
+        .highlight_symbol(">> ")
+        .style(CYBERPUNK_STYLE_CYAN);
+    let mut choices_state = choice_popup.choices.state.clone();
+
+    f.render_widget(message_widget, chunks[0]);
+    f.render_stateful_widget(choices_list, chunks[1], &mut choices_state);
+}
+
+/// Draws what an install, uninstall or profile switch will change before it runs
+pub fn draw_plan_preview<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let plan_preview = app.plan_preview.as_ref().unwrap();
+    let popup_area = centered_rect(80, 80, f.size());
+    f.render_widget(Clear, popup_area);
+
+    let lines: Vec<Spans> = plan_preview.plan
+        .lines()
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let style = if index == 0 {
+                CYBERPUNK_STYLE_YELLOW
+            } else if line.starts_with(' ') {
+                CYBERPUNK_STYLE_CYAN
+            } else {
+                CYBERPUNK_STYLE_PINK
+            };
+            Spans::from(Span::styled(line, style))
+        })
+        .collect();
+    let plan_widget = Paragraph::new(lines)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(CYBERPUNK_STYLE_PINK)
+            .title(format!("{} - <Enter> to go ahead, <Esc> to cancel, <Up>/<Down> to scroll", plan_preview.plan.title)))
+        .scroll((plan_preview.scroll, 0));
+    f.render_widget(plan_widget, popup_area);
+}
+
+pub fn draw_fomod_wizard<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let fomod_wizard = app.fomod_wizard.as_ref().unwrap();
+    let step = fomod_wizard.step();
+    let flags = fomod_wizard.flags();
+    let (position, total) = fomod_wizard.progress();
+    let popup_area = centered_rect(80, 80, f.size());
+    f.render_widget(Clear, popup_area);
+    f.render_widget(Block::default()
+        .borders(Borders::ALL)
+        .border_style(CYBERPUNK_STYLE_PINK)
+        .title(format!("{} - {} ({}/{})", fomod_wizard.config.display_name(), step.name, position, total)), popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .margin(1)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)].as_ref())
+        .split(popup_area);
+
+    // every group starts with a header line above its first option
+    let items: Vec<ListItem> = fomod_wizard.options
+        .items
+        .iter()
+        .map(|(group_index, plugin_index)| {
+            let group = &step.groups[*group_index];
+            let plugin = &group.plugins[*plugin_index];
+            let is_selected = fomod_wizard.is_selected(*group_index, *plugin_index, &flags);
+            let marker = match (group.group_type.is_single(), is_selected) {
+                (true, true) => "(*)",
+                (true, false) => "( )",
+                (false, true) => "[x]",
+                (false, false) => "[ ]",
+            };
+            let mut lines = vec![];
+            if *plugin_index == 0 {
+                lines.push(Spans::from(Span::styled(
+                    format!("{} - {}", group.name, group.group_type.to_str()),
+                    CYBERPUNK_STYLE_YELLOW,
+                )));
+            }
+            lines.push(Spans::from(Span::raw(format!("  {} {}", marker, plugin.name))));
+            ListItem::new(Text::from(lines))
+        })
+        .collect();
+    let options_list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("<Space> to toggle, <Enter> next, <Esc> back"))
+        .highlight_style(CYBERPUNK_STYLE_PINK)
+        .highlight_symbol(">> ")
+        .style(CYBERPUNK_STYLE_CYAN);
+    let mut options_state = fomod_wizard.options.state.clone();
+
+    let description = match fomod_wizard.selected_option() {
+        Some((group_index, plugin_index)) => {
+            let plugin = &step.groups[group_index].plugins[plugin_index];
+            let plugin_type = plugin.type_descriptor.plugin_type(&flags, &fomod_wizard.cyberpunk_dir);
+            vec![
+                Spans::from(Span::styled(plugin_type.to_str().to_string(), CYBERPUNK_STYLE_PINK)),
+                Spans::from(Span::raw("")),
+                Spans::from(Span::raw(plugin.description.clone())),
+            ]
         }
+        None => vec![],
     };
-    let mod_install_status_widget = Paragraph::new(Text::raw(mod_install_status))
-        .block(Block::default().borders(Borders::ALL).title("Mod Install Status"))
+    let description_widget = Paragraph::new(description)
+        .block(Block::default().borders(Borders::ALL).title("Description"))
+        .style(CYBERPUNK_STYLE_YELLOW)
+        .wrap(Wrap { trim: true });
+
+    f.render_stateful_widget(options_list, chunks[0], &mut options_state);
+    f.render_widget(description_widget, chunks[1]);
+}
+
+/// Draws which frameworks are in the Cyberpunk folder and their versions
+pub fn draw_frameworks<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(10), Constraint::Length(3)].as_ref())
+        .split(f.size());
+
+    let rows: Vec<ListItem> = app
+        .frameworks
+        .iter()
+        .map(|framework_info| {
+            let (state, style) = if framework_info.installed {
+                ("Installed", FOCUS_STYLE)
+            } else {
+                ("Missing", ERROR_TEXT_STYLE)
+            };
+            let version = framework_info.version.clone().unwrap_or_else(|| "-".to_string());
+            let installed_by = framework_info
+                .installed_by
+                .as_ref()
+                .map(|mod_name| format!("installed from {}", mod_name))
+                .unwrap_or_default();
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:<22}", framework_info.framework.to_str()), CYBERPUNK_STYLE_CYAN),
+                Span::styled(format!("{:<12}", state), style),
+                Span::styled(format!("{:<14}", version), CYBERPUNK_STYLE_YELLOW),
+                Span::styled(installed_by, LOG_DEBUG_STYLE),
+            ]))
+        })
+        .collect();
+    let frameworks_list = List::new(rows)
+        .block(Block::default().borders(Borders::ALL).title("Frameworks (<w> or <Esc> to go back)"));
+
+    let cyberpunk_folder = app.cyberpunk_folder.clone().unwrap_or_default();
+    let cyberpunk_folder_widget = Paragraph::new(Text::raw(cyberpunk_folder.to_string_lossy().to_string()))
+        .block(Block::default().borders(Borders::ALL).title("Cyberpunk Folder"))
         .style(CYBERPUNK_STYLE_YELLOW)
         .wrap(Wrap { trim: true });
 
-    f.render_widget(mod_name_widget, chunks[0]);
-    f.render_stateful_widget(items_list, chunks[1], mod_options_state);
-    f.render_widget(mod_install_status_widget, chunks[2]);
-}
\ No newline at end of file
+    f.render_widget(draw_title(false), chunks[0]);
+    f.render_widget(frameworks_list, chunks[1]);
+    f.render_widget(cyberpunk_folder_widget, chunks[2]);
+}
+
+/// Draws the jobs of this session, newest first, with the progress of the running operation
+pub fn draw_jobs<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(10), Constraint::Length(3)].as_ref())
+        .split(f.size());
+
+    let rows: Vec<ListItem> = app
+        .jobs
+        .all()
+        .iter()
+        .rev()
+        .map(|job| {
+            let style = match job.state {
+                JobState::Queued => CYBERPUNK_STYLE_YELLOW,
+                JobState::Running => CYBERPUNK_STYLE_CYAN,
+                JobState::Finished => FOCUS_STYLE,
+                JobState::Failed(_) => ERROR_TEXT_STYLE,
+            };
+            let reason = match &job.state {
+                JobState::Failed(reason) => reason.lines().next().unwrap_or_default().to_string(),
+                _ => String::new(),
+            };
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:<6}", job.id), LOG_DEBUG_STYLE),
+                Span::styled(format!("{:<10}", job.state.to_str()), style),
+                Span::styled(format!("{:<48}", job.description), CYBERPUNK_STYLE_CYAN),
+                Span::styled(reason, ERROR_TEXT_STYLE),
+            ]))
+        })
+        .collect();
+    let jobs_list = List::new(rows)
+        .block(Block::default().borders(Borders::ALL).title("Jobs (<j> or <Esc> to go back)"));
+
+    f.render_widget(draw_title(false), chunks[0]);
+    f.render_widget(jobs_list, chunks[1]);
+    match app.progress.get().filter(|_| app.is_loading()) {
+        Some(progress) => {
+            let progress_gauge = Gauge::default()
+                .block(Block::default().borders(Borders::ALL).title("Progress (<x> to cancel)"))
+                .gauge_style(CYBERPUNK_STYLE_CYAN)
+                .ratio(progress.ratio())
+                .label(progress.label());
+            f.render_widget(progress_gauge, chunks[2]);
+        }
+        None => {
+            let idle_widget = Paragraph::new(Text::raw("No install, uninstall or repair running"))
+                .block(Block::default().borders(Borders::ALL).title("Progress"))
+                .style(LOG_DEBUG_STYLE);
+            f.render_widget(idle_widget, chunks[2]);
+        }
+    }
+}
+
+/// Draws the archives of `archive/pc/mod` in the order the game loads them
+pub fn draw_load_order<B: Backend>(f: &mut Frame<B>, app: &App, load_order_state: &mut ListState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(10), Constraint::Length(3)].as_ref())
+        .split(f.size());
+
+    let rows: Vec<ListItem> = app
+        .state
+        .load_order
+        .items
+        .iter()
+        .enumerate()
+        .map(|(index, (archive, owner))| {
+            let owner = owner
+                .as_ref()
+                .map(|mod_name| format!("installed from {}", mod_name))
+                .unwrap_or_default();
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>4}  ", index + 1), CYBERPUNK_STYLE_YELLOW_DARK),
+                Span::styled(format!("{:<48}", archive), CYBERPUNK_STYLE_CYAN),
+                Span::styled(owner, LOG_DEBUG_STYLE),
+            ]))
+        })
+        .collect();
+    let load_order_list = List::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Load Order, first wins (<+>/<-> to move, <o> or <Esc> to go back)"),
+        )
+        .highlight_style(CYBERPUNK_STYLE_PINK)
+        .highlight_symbol(">> ");
+
+    let position_widget = Paragraph::new(Text::raw(format!(
+        "New archives go to the {} (<Space> to change)",
+        app.new_archive_position.to_str()
+    )))
+    .block(Block::default().borders(Borders::ALL).title("New Installs"))
+    .style(CYBERPUNK_STYLE_YELLOW)
+    .wrap(Wrap { trim: true });
+
+    f.render_widget(draw_title(false), chunks[0]);
+    f.render_stateful_widget(load_order_list, chunks[1], load_order_state);
+    f.render_widget(position_widget, chunks[2]);
+}
+
+/// Draws the REDmods in deploy order next to the `info.json` of the selected one
+pub fn draw_redmods<B: Backend>(f: &mut Frame<B>, app: &App, redmods_state: &mut ListState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(10), Constraint::Length(3)].as_ref())
+        .split(f.size());
+    let body_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(chunks[1]);
+
+    let redmods = &app.state.redmods.items;
+    let rows: Vec<ListItem> = redmods
+        .iter()
+        .enumerate()
+        .map(|(index, redmod)| {
+            let (state, style) = if redmod.enabled {
+                ("Enabled", FOCUS_STYLE)
+            } else {
+                ("Disabled", ERROR_TEXT_STYLE)
+            };
+            let version = redmod.info.as_ref().map(|info| info.version.clone()).unwrap_or_default();
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:>4}  ", index + 1), CYBERPUNK_STYLE_YELLOW_DARK),
+                Span::styled(format!("{:<32}", redmod.display_name()), CYBERPUNK_STYLE_CYAN),
+                Span::styled(format!("{:<10}", state), style),
+                Span::styled(version, CYBERPUNK_STYLE_YELLOW),
+            ]))
+        })
+        .collect();
+    let redmods_list = List::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("REDmods (<Space> to enable/disable, <+>/<-> to move, <r> or <Esc> to go back)"),
+        )
+        .highlight_style(CYBERPUNK_STYLE_PINK)
+        .highlight_symbol(">> ");
+
+    let selected = redmods_state.selected().and_then(|selected| redmods.get(selected));
+    let details = match selected {
+        Some(redmod) => {
+            let mut lines = vec![
+                Spans::from(Span::styled(redmod.display_name().to_string(), CYBERPUNK_STYLE_CYAN)),
+                Spans::from(Span::raw(format!("Folder: mods/{}", redmod.folder))),
+            ];
+            if let Some(mod_name) = &redmod.installed_by {
+                lines.push(Spans::from(Span::styled(format!("Installed from {}", mod_name), LOG_DEBUG_STYLE)));
+            }
+            match &redmod.info {
+                Some(info) => {
+                    lines.push(Spans::from(Span::raw(format!("Version: {}", info.version))));
+                    lines.push(Spans::from(Span::raw("")));
+                    lines.push(Spans::from(Span::raw(info.description.clone())));
+                    if !info.custom_sounds.is_empty() {
+                        lines.push(Spans::from(Span::raw("")));
+                        lines.push(Spans::from(Span::styled(
+                            format!("Custom sounds ({})", info.custom_sounds.len()),
+                            CYBERPUNK_STYLE_PINK,
+                        )));
+                        for custom_sound in &info.custom_sounds {
+                            let file = custom_sound.file.clone().unwrap_or_else(|| "-".to_string());
+                            lines.push(Spans::from(Span::raw(format!("{} ({}): {}", custom_sound.name, custom_sound.sound_type, file))));
+                        }
+                    }
+                }
+                None => lines.push(Spans::from(Span::styled("info.json could not be read", ERROR_TEXT_STYLE))),
+            }
+            lines
+        }
+        None => vec![],
+    };
+    let details_widget = Paragraph::new(details)
+        .block(Block::default().borders(Borders::ALL).title("Info"))
+        .style(CYBERPUNK_STYLE_YELLOW)
+        .wrap(Wrap { trim: true });
+
+    let deploy_order: Vec<&str> = redmods
+        .iter()
+        .filter(|redmod| redmod.enabled)
+        .map(|redmod| redmod.folder.as_str())
+        .collect();
+    let deploy_order_widget = Paragraph::new(Text::raw(deploy_order.join(", ")))
+        .block(Block::default().borders(Borders::ALL).title("Deploy Order"))
+        .style(CYBERPUNK_STYLE_YELLOW)
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(draw_title(false), chunks[0]);
+    f.render_stateful_widget(redmods_list, body_chunks[0], redmods_state);
+    f.render_widget(details_widget, body_chunks[1]);
+    f.render_widget(deploy_order_widget, chunks[2]);
+}
+
+/// Draws the saved profiles and the input for the name of a new one
+pub fn draw_profiles<B: Backend>(f: &mut Frame<B>, app: &App, profiles_state: &mut ListState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(10), Constraint::Length(3)].as_ref())
+        .split(f.size());
+    let body_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[1]);
+
+    let profiles = &app.state.profiles.items;
+    let rows: Vec<ListItem> = profiles
+        .iter()
+        .map(|profile| {
+            let is_active = app.active_profile.as_ref() == Some(&profile.name);
+            let enabled = profile.mods.iter().filter(|profile_mod| profile_mod.enabled).count();
+            ListItem::new(Spans::from(vec![
+                Span::styled(format!("{:<32}", profile.name), CYBERPUNK_STYLE_CYAN),
+                Span::styled(format!("{:<16}", format!("{} mods", enabled)), CYBERPUNK_STYLE_YELLOW),
+                Span::styled(if is_active { "Active" } else { "" }, FOCUS_STYLE),
+            ]))
+        })
+        .collect();
+    let profiles_list = List::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Profiles (<Enter> to switch, <Del> to delete, <p> or <Esc> to go back)"),
+        )
+        .highlight_style(CYBERPUNK_STYLE_PINK)
+        .highlight_symbol(">> ");
+
+    let selected = profiles_state.selected().and_then(|selected| profiles.get(selected));
+    let mod_items: Vec<ListItem> = selected
+        .map(|profile| {
+            profile
+                .mods
+                .iter()
+                .map(|profile_mod| {
+                    let (state, style) = if profile_mod.enabled {
+                        ("Enabled", FOCUS_STYLE)
+                    } else {
+                        ("Disabled", LOG_WARN_STYLE)
+                    };
+                    ListItem::new(Spans::from(vec![
+                        Span::styled(format!("{:<10}", state), style),
+                        Span::styled(profile_mod.mod_name.clone(), CYBERPUNK_STYLE_YELLOW),
+                    ]))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let mods_list = List::new(mod_items).block(Block::default().borders(Borders::ALL).title("Mods"));
+
+    let input_style = if app.state.status == AppStatus::UserInput {
+        CYBERPUNK_STYLE_PINK
+    } else {
+        CYBERPUNK_STYLE_YELLOW
+    };
+    let input_widget = Paragraph::new(Text::raw(app.state.current_input.clone()))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Save installed mods as (<i> to type a name, <Enter> to save)"),
+        )
+        .style(input_style);
+    if app.state.status == AppStatus::UserInput {
+        f.set_cursor(
+            chunks[2].x + app.state.cursor_position.unwrap_or(0) as u16 + 1,
+            chunks[2].y + 1,
+        );
+    }
+
+    f.render_widget(draw_title(false), chunks[0]);
+    f.render_stateful_widget(profiles_list, body_chunks[0], profiles_state);
+    f.render_widget(mods_list, body_chunks[1]);
+    f.render_widget(input_widget, chunks[2]);
+}